@@ -19,6 +19,7 @@ use std::{env, mem, path::PathBuf, sync::Arc, time::Duration};
 use telemetry_events::{
     ActionEvent, AppEvent, AssistantEvent, CallEvent, EditEvent, EditorEvent, Event,
     EventRequestBody, EventWrapper, ExtensionEvent, InlineCompletionEvent, ReplEvent, SettingEvent,
+    TaskEvent,
 };
 use util::{ResultExt, TryFutureExt};
 use worktree::{UpdatedEntriesSet, WorktreeId};
@@ -487,6 +488,15 @@ impl Telemetry {
         self.report_event(event)
     }
 
+    pub fn report_task_event(self: &Arc<Self>, language: Option<String>, is_remote: bool) {
+        let event = Event::Task(TaskEvent {
+            language,
+            is_remote,
+        });
+
+        self.report_event(event)
+    }
+
     fn report_event(self: &Arc<Self>, event: Event) {
         let mut state = self.state.lock();
 
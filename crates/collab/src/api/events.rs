@@ -483,7 +483,7 @@ pub async fn post_events(
                         checksum_matched,
                     ))
             }
-            Event::Cpu(_) | Event::Memory(_) => continue,
+            Event::Cpu(_) | Event::Memory(_) | Event::Task(_) => continue,
             Event::App(event) => to_upload.app_events.push(AppEventRow::from_event(
                 event.clone(),
                 wrapper,
@@ -1539,6 +1539,10 @@ fn for_snowflake(
                 "Kernel Status Changed".to_string(),
                 serde_json::to_value(e).unwrap(),
             ),
+            Event::Task(e) => (
+                "Task Launched".to_string(),
+                serde_json::to_value(e).unwrap(),
+            ),
         };
 
         if let serde_json::Value::Object(ref mut map) = event_properties {
@@ -1,5 +1,7 @@
 use crate::Editor;
 
+use std::{path::Path, sync::Arc};
+
 use gpui::{Task as AsyncTask, WindowContext};
 use project::Location;
 use task::{TaskContext, TaskVariables, VariableName};
@@ -75,15 +77,42 @@ fn task_context_with_editor(
 }
 
 pub fn task_context(workspace: &Workspace, cx: &mut WindowContext<'_>) -> AsyncTask<TaskContext> {
+    let first_worktree_root = workspace
+        .visible_worktrees(cx)
+        .map(|worktree| worktree.read(cx))
+        .find(|worktree| worktree.root_entry().is_some_and(|entry| entry.is_dir()))
+        .map(|worktree| worktree.abs_path());
     let Some(editor) = workspace
         .active_item(cx)
         .and_then(|item| item.act_as::<Editor>(cx))
     else {
-        return AsyncTask::ready(TaskContext::default());
+        return AsyncTask::ready(with_worktree_root(
+            TaskContext::default(),
+            first_worktree_root,
+        ));
     };
     editor.update(cx, |editor, cx| {
         let context_task = task_context_with_editor(editor, cx);
-        cx.background_executor()
-            .spawn(async move { context_task.await.unwrap_or_default() })
+        cx.background_executor().spawn(async move {
+            with_worktree_root(context_task.await.unwrap_or_default(), first_worktree_root)
+        })
     })
 }
+
+/// Sets `ZED_WORKTREE_ROOT` to the given worktree root, unless the context has it already
+/// from the worktree of the active file.
+fn with_worktree_root(mut context: TaskContext, worktree_root: Option<Arc<Path>>) -> TaskContext {
+    if context
+        .task_variables
+        .get(&VariableName::WorktreeRoot)
+        .is_none()
+    {
+        if let Some(worktree_root) = worktree_root {
+            context.task_variables.insert(
+                VariableName::WorktreeRoot,
+                worktree_root.to_string_lossy().to_string(),
+            );
+        }
+    }
+    context
+}
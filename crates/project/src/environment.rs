@@ -1,6 +1,11 @@
-use futures::{future::Shared, FutureExt};
-use std::{path::Path, sync::Arc};
-use util::ResultExt;
+use fs::Fs;
+use futures::{future::Shared, stream, FutureExt, StreamExt};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use util::{paths::home_dir, ResultExt};
 
 use collections::HashMap;
 use gpui::{AppContext, Context, Model, ModelContext, Task};
@@ -14,11 +19,18 @@ use crate::{
 
 pub struct ProjectEnvironment {
     cli_environment: Option<HashMap<String, String>>,
+    /// Whether the CLI environment predates a change of the shell profiles, and has to be reloaded.
+    cli_environment_stale: bool,
     get_environment_task: Option<Shared<Task<Option<HashMap<String, String>>>>>,
     cached_shell_environments: HashMap<WorktreeId, HashMap<String, String>>,
     environment_error_messages: HashMap<WorktreeId, EnvironmentErrorMessage>,
+    /// Whether any of the files the shells read `PATH` from has changed since the environments were loaded.
+    environment_files_changed: bool,
+    environment_files_watcher: Option<Task<()>>,
 }
 
+const ENVIRONMENT_FILES_LATENCY: Duration = Duration::from_millis(500);
+
 impl ProjectEnvironment {
     pub fn new(
         worktree_store: &Model<WorktreeStore>,
@@ -35,9 +47,12 @@ impl ProjectEnvironment {
 
             Self {
                 cli_environment,
+                cli_environment_stale: false,
                 get_environment_task: None,
                 cached_shell_environments: Default::default(),
                 environment_error_messages: Default::default(),
+                environment_files_changed: false,
+                environment_files_watcher: None,
             }
         })
     }
@@ -58,6 +73,55 @@ impl ProjectEnvironment {
         self.environment_error_messages.remove(&worktree_id);
     }
 
+    /// Starts watching the system and the user's shell profiles for changes,
+    /// e.g. a newly installed tool adding itself to `PATH`.
+    pub(crate) fn watch_environment_files(&mut self, fs: Arc<dyn Fs>, cx: &mut ModelContext<Self>) {
+        self.environment_files_watcher = Some(cx.spawn(|this, mut cx| async move {
+            let files = environment_files();
+            let mut streams = Vec::new();
+            let mut watchers = Vec::new();
+            // The files missing yet are watched too, so that a profile created later is noticed.
+            for path in &files {
+                let (events, watcher) = fs.watch(path, ENVIRONMENT_FILES_LATENCY).await;
+                streams.push(events);
+                watchers.push(watcher);
+            }
+            let mut events = stream::select_all(streams);
+            while let Some(events) = events.next().await {
+                // The watchers report the changes of the other files in the watched files' directories too.
+                if !events
+                    .iter()
+                    .any(|event| files.iter().any(|file| event.path.starts_with(file)))
+                {
+                    continue;
+                }
+                let updated = this.update(&mut cx, |this, _| {
+                    this.environment_files_changed = true;
+                });
+                if updated.is_err() {
+                    break;
+                }
+            }
+            drop(watchers);
+        }));
+    }
+
+    #[cfg_attr(not(feature = "terminal-integration"), allow(dead_code))]
+    pub(crate) fn environment_files_changed(&self) -> bool {
+        self.environment_files_changed
+    }
+
+    /// Forgets the environments loaded so far, so that the next ones are loaded anew from the shells.
+    /// The environment inherited from the CLI cannot be read again: it is replaced
+    /// with the one of a fresh login shell, instead of keeping the `PATH` from before the change.
+    #[cfg_attr(not(feature = "terminal-integration"), allow(dead_code))]
+    pub(crate) fn invalidate_cache(&mut self) {
+        self.cli_environment_stale = self.cli_environment.is_some();
+        self.get_environment_task = None;
+        self.cached_shell_environments.clear();
+        self.environment_files_changed = false;
+    }
+
     /// Returns the inherited CLI environment, if this project was opened from the Zed CLI.
     pub(crate) fn get_cli_environment(&self) -> Option<HashMap<String, String>> {
         if let Some(mut env) = self.cli_environment.clone() {
@@ -113,6 +177,9 @@ impl ProjectEnvironment {
 
         let cli_environment = self.get_cli_environment();
         if let Some(environment) = cli_environment {
+            if std::mem::take(&mut self.cli_environment_stale) {
+                return self.reload_cli_environment(environment, cx);
+            }
             cx.spawn(|_, _| async move {
                 let path = environment
                     .get("PATH")
@@ -131,6 +198,39 @@ impl ProjectEnvironment {
         }
     }
 
+    /// Loads the environment of a login shell in the home directory, to replace the stale CLI environment.
+    /// The stale environment is kept, if the shell fails to load.
+    fn reload_cli_environment(
+        &mut self,
+        stale_environment: HashMap<String, String>,
+        cx: &ModelContext<Self>,
+    ) -> Task<Option<HashMap<String, String>>> {
+        let load_direnv = ProjectSettings::get_global(cx).load_direnv.clone();
+        cx.spawn(|this, mut cx| async move {
+            let (shell_env, _) = cx
+                .background_executor()
+                .spawn(async move { load_shell_environment(home_dir(), &load_direnv).await })
+                .await;
+            let Some(mut shell_env) = shell_env else {
+                return Some(stale_environment);
+            };
+            let path = shell_env
+                .get("PATH")
+                .map(|path| path.as_str())
+                .unwrap_or_default();
+            log::info!(
+                "replacing project environment variables from CLI with a login shell's. PATH={:?}",
+                path
+            );
+            this.update(&mut cx, |this, _| {
+                this.cli_environment = Some(shell_env.clone());
+            })
+            .log_err();
+            set_origin_marker(&mut shell_env, EnvironmentOrigin::Cli);
+            Some(shell_env)
+        })
+    }
+
     fn get_worktree_env(
         &mut self,
         worktree_id: WorktreeId,
@@ -184,6 +284,28 @@ impl ProjectEnvironment {
     }
 }
 
+/// The files that the shells read `PATH` from, at login or at startup.
+fn environment_files() -> Vec<PathBuf> {
+    let mut files = vec![
+        PathBuf::from("/etc/paths"),
+        PathBuf::from("/etc/paths.d"),
+        PathBuf::from("/etc/profile"),
+    ];
+    files.extend(
+        [
+            ".profile",
+            ".bash_profile",
+            ".bashrc",
+            ".zprofile",
+            ".zshrc",
+            ".config/fish/config.fish",
+        ]
+        .into_iter()
+        .map(|file| home_dir().join(file)),
+    );
+    files
+}
+
 fn set_origin_marker(env: &mut HashMap<String, String>, origin: EnvironmentOrigin) {
     env.insert(ZED_ENVIRONMENT_ORIGIN_MARKER.to_string(), origin.into());
 }
@@ -9,8 +9,10 @@ pub mod lsp_store;
 pub mod prettier_store;
 pub mod project_settings;
 pub mod search;
+pub mod task_history;
 mod task_inventory;
 pub mod task_store;
+#[cfg(feature = "terminal-integration")]
 pub mod terminals;
 pub mod toolchain_store;
 pub mod worktree_store;
@@ -41,8 +43,8 @@ use image_store::{ImageItemEvent, ImageStoreEvent};
 
 use git::{blame::Blame, repository::GitRepository};
 use gpui::{
-    AnyModel, AppContext, AsyncAppContext, BorrowAppContext, Context as _, EventEmitter, Hsla,
-    Model, ModelContext, SharedString, Task, WeakModel, WindowContext,
+    AnyModel, AppContext, AsyncAppContext, BorrowAppContext, Context as _, EntityId, EventEmitter,
+    Hsla, Model, ModelContext, SharedString, Task, WeakModel, WindowContext,
 };
 use itertools::Itertools;
 use language::{
@@ -79,7 +81,10 @@ use std::{
     sync::Arc,
     time::Duration,
 };
+#[cfg(feature = "terminal-integration")]
+use task::TaskId;
 use task_store::TaskStore;
+#[cfg(feature = "terminal-integration")]
 use terminals::Terminals;
 use text::{Anchor, BufferId};
 use toolchain_store::EmptyToolchainStore;
@@ -157,6 +162,7 @@ pub struct Project {
     buffers_needing_diff: HashSet<WeakModel<Buffer>>,
     git_diff_debouncer: DebouncedDelay<Self>,
     remotely_created_models: Arc<Mutex<RemotelyCreatedModels>>,
+    #[cfg(feature = "terminal-integration")]
     terminals: Terminals,
     node: Option<NodeRuntime>,
     search_history: SearchHistory,
@@ -272,6 +278,19 @@ pub enum Event {
     RefreshInlayHints,
     RevealInProjectPanel(ProjectEntryId),
     SnippetEdit(BufferId, Vec<(lsp::Range, Snippet)>),
+    #[cfg(feature = "terminal-integration")]
+    TaskTestResult {
+        task_id: TaskId,
+        result: terminal::test_output::TestResult,
+    },
+    /// [`Project::active_project_directory`] has changed, after the active entry has settled.
+    ProjectDirectoryChanged {
+        path: Option<Arc<Path>>,
+    },
+    /// A terminal was closed to make room for a new one, due to `terminal.max_terminals`.
+    TerminalEvicted {
+        entity_id: EntityId,
+    },
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
@@ -628,6 +647,9 @@ impl Project {
             });
 
             let environment = ProjectEnvironment::new(&worktree_store, env, cx);
+            environment.update(cx, |environment, cx| {
+                environment.watch_environment_files(fs.clone(), cx)
+            });
             let toolchain_store = cx.new_model(|cx| {
                 ToolchainStore::local(
                     languages.clone(),
@@ -695,8 +717,15 @@ impl Project {
                 ssh_client: None,
                 buffers_needing_diff: Default::default(),
                 git_diff_debouncer: DebouncedDelay::new(),
+                #[cfg(feature = "terminal-integration")]
                 terminals: Terminals {
-                    local_handles: Vec::new(),
+                    local_handles: HashMap::default(),
+                    task_outputs: HashMap::default(),
+                    env_hooks: Vec::new(),
+                    last_project_directory: None,
+                    project_directory_check: None,
+                    venv_cache: HashMap::default(),
+                    tasks_running: false,
                 },
                 node: Some(node),
                 search_history: Self::new_search_history(),
@@ -828,8 +857,15 @@ impl Project {
                 ssh_client: Some(ssh.clone()),
                 buffers_needing_diff: Default::default(),
                 git_diff_debouncer: DebouncedDelay::new(),
+                #[cfg(feature = "terminal-integration")]
                 terminals: Terminals {
-                    local_handles: Vec::new(),
+                    local_handles: HashMap::default(),
+                    task_outputs: HashMap::default(),
+                    env_hooks: Vec::new(),
+                    last_project_directory: None,
+                    project_directory_check: None,
+                    venv_cache: HashMap::default(),
+                    tasks_running: false,
                 },
                 node: Some(node),
                 search_history: Self::new_search_history(),
@@ -1033,8 +1069,15 @@ impl Project {
                 },
                 buffers_needing_diff: Default::default(),
                 git_diff_debouncer: DebouncedDelay::new(),
+                #[cfg(feature = "terminal-integration")]
                 terminals: Terminals {
-                    local_handles: Vec::new(),
+                    local_handles: HashMap::default(),
+                    task_outputs: HashMap::default(),
+                    env_hooks: Vec::new(),
+                    last_project_directory: None,
+                    project_directory_check: None,
+                    venv_cache: HashMap::default(),
+                    tasks_running: false,
                 },
                 node: None,
                 search_history: Self::new_search_history(),
@@ -1294,6 +1337,9 @@ impl Project {
     }
 
     pub fn supports_terminal(&self, _cx: &AppContext) -> bool {
+        if !cfg!(feature = "terminal-integration") {
+            return false;
+        }
         if self.is_local() {
             return true;
         }
@@ -2205,9 +2251,13 @@ impl Project {
             WorktreeStoreEvent::WorktreeAdded(worktree) => {
                 self.on_worktree_added(worktree, cx);
                 cx.emit(Event::WorktreeAdded);
+                #[cfg(feature = "terminal-integration")]
+                self.schedule_project_directory_check(cx);
             }
             WorktreeStoreEvent::WorktreeRemoved(_, id) => {
                 cx.emit(Event::WorktreeRemoved(*id));
+                #[cfg(feature = "terminal-integration")]
+                self.schedule_project_directory_check(cx);
             }
             WorktreeStoreEvent::WorktreeReleased(_, id) => {
                 self.on_worktree_released(*id, cx);
@@ -2227,6 +2277,8 @@ impl Project {
         cx.observe(worktree, |_, _, cx| cx.notify()).detach();
         cx.subscribe(worktree, |project, worktree, event, cx| match event {
             worktree::Event::UpdatedEntries(changes) => {
+                #[cfg(feature = "terminal-integration")]
+                project.invalidate_venv_cache(&worktree.read(cx).abs_path(), changes);
                 cx.emit(Event::WorktreeUpdatedEntries(
                     worktree.read(cx).id(),
                     changes.clone(),
@@ -3385,6 +3437,8 @@ impl Project {
                 lsp_store.set_active_entry(new_active_entry);
             });
             cx.emit(Event::ActiveEntryChanged(new_active_entry));
+            #[cfg(feature = "terminal-integration")]
+            self.schedule_project_directory_check(cx);
         }
     }
 
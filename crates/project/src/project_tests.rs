@@ -181,6 +181,118 @@ async fn test_editorconfig_support(cx: &mut gpui::TestAppContext) {
     });
 }
 
+#[cfg(feature = "terminal-integration")]
+#[gpui::test]
+async fn test_find_venv_directory(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let bin_dir = if cfg!(windows) { "Scripts" } else { "bin" };
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/the-root",
+        json!({
+            "with-venv": {
+                "env": { "lib": {} },
+                ".venv": { bin_dir: { "python": "" } },
+            },
+            "without-venv": {
+                "env": { "lib": {} },
+            },
+            "with-uv-venv": {
+                "env": { bin_dir: { "python": "" } },
+                ".venv": {
+                    bin_dir: { "python": "" },
+                    "pyvenv.cfg": "home = /usr/bin\nuv = 0.4.20\n",
+                },
+            },
+        }),
+    )
+    .await;
+    let project = Project::test(fs.clone(), ["/the-root".as_ref()], cx).await;
+    cx.run_until_parked();
+
+    let venv_settings = terminal::terminal_settings::VenvSettingsContent {
+        activate_script: terminal::terminal_settings::ActivateScript::Default,
+        directories: vec![PathBuf::from("env"), PathBuf::from(".venv")],
+        cache_ttl: Duration::ZERO,
+    };
+    let find_venv = |path: &str, cx: &mut gpui::TestAppContext| {
+        project.update(cx, |project, cx| {
+            project.find_venv_directory(Path::new(path).into(), venv_settings.clone(), cx)
+        })
+    };
+
+    // `env` has no `bin` directory, so the next candidate is used.
+    assert_eq!(
+        find_venv("/the-root/with-venv", cx).await,
+        Some(PathBuf::from("/the-root/with-venv/.venv"))
+    );
+    assert_eq!(find_venv("/the-root/without-venv", cx).await, None);
+    // Both directories are venvs, the one created by `uv` is preferred over the first configured one.
+    assert_eq!(
+        find_venv("/the-root/with-uv-venv", cx).await,
+        Some(PathBuf::from("/the-root/with-uv-venv/.venv"))
+    );
+}
+
+#[cfg(feature = "terminal-integration")]
+#[gpui::test]
+async fn test_project_directory_skips_git_directory(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/the-root",
+        json!({
+            ".git": {
+                "COMMIT_EDITMSG": "message",
+            },
+            "src": {
+                "main.rs": "fn main() {}",
+            },
+        }),
+    )
+    .await;
+    fs.insert_tree("/other", json!({ "file.txt": "text" }))
+        .await;
+
+    let project = Project::test(fs.clone(), ["/the-root/.git".as_ref()], cx).await;
+    project.update(cx, |project, cx| {
+        assert_eq!(project.active_project_directory(cx), None);
+        assert_eq!(project.default_project_directory(cx), None);
+    });
+
+    let project = Project::test(
+        fs.clone(),
+        ["/the-root/.git".as_ref(), "/the-root".as_ref()],
+        cx,
+    )
+    .await;
+    project.update(cx, |project, cx| {
+        assert_eq!(
+            project.active_project_directory(cx),
+            Some(PathBuf::from("/the-root"))
+        );
+    });
+
+    let project = Project::test(
+        fs.clone(),
+        [
+            "/the-root/.git/COMMIT_EDITMSG".as_ref(),
+            "/other/file.txt".as_ref(),
+        ],
+        cx,
+    )
+    .await;
+    project.update(cx, |project, cx| {
+        assert_eq!(project.active_project_directory(cx), None);
+        assert_eq!(
+            project.default_project_directory(cx),
+            Some(PathBuf::from("/other"))
+        );
+    });
+}
+
 #[gpui::test]
 async fn test_managing_project_specific_settings(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -0,0 +1,159 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use collections::HashMap;
+use db::{define_connection, query, sqlez_macros::sql};
+use gpui::{AppContext, Task};
+use task::TaskId;
+use util::ResultExt;
+
+use crate::Project;
+
+/// How many of the latest runs of a task are used to compute its average duration.
+const AVERAGE_DURATION_RUNS: usize = 10;
+/// How many of the latest runs of a task are kept in the history, the older ones are deleted.
+const KEPT_RUNS_PER_TASK: usize = 100;
+
+/// A single finished run of a task.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskRunRecord {
+    pub task_id: TaskId,
+    pub task_label: String,
+    pub start_time: SystemTime,
+    pub duration: Duration,
+    /// `None` if the task was cancelled or otherwise did not report its exit code.
+    pub exit_code: Option<i32>,
+}
+
+define_connection!(pub static ref TASK_HISTORY_DB: TaskHistoryDb<()> =
+    &[sql!(
+        CREATE TABLE IF NOT EXISTS task_runs(
+            task_id TEXT NOT NULL,
+            task_label TEXT NOT NULL,
+            start_time INTEGER NOT NULL,
+            duration_ms INTEGER NOT NULL,
+            exit_code INTEGER
+        ) STRICT;
+        CREATE INDEX IF NOT EXISTS task_runs_by_task_id ON task_runs(task_id, start_time);
+    )];
+);
+
+impl TaskHistoryDb {
+    query! {
+        pub async fn save_task_run(
+            task_id: String,
+            task_label: String,
+            start_time: i64,
+            duration_ms: i64,
+            exit_code: Option<i32>
+        ) -> Result<()> {
+            INSERT INTO task_runs(task_id, task_label, start_time, duration_ms, exit_code)
+            VALUES (?, ?, ?, ?, ?)
+        }
+    }
+
+    query! {
+        pub async fn prune_task_runs(task_id: String, kept_runs: i64) -> Result<()> {
+            DELETE FROM task_runs
+            WHERE task_id = ?1 AND rowid NOT IN (
+                SELECT rowid
+                FROM task_runs
+                WHERE task_id = ?1
+                ORDER BY start_time DESC
+                LIMIT ?2
+            )
+        }
+    }
+
+    query! {
+        pub async fn average_task_run_durations(runs: i64) -> Result<Vec<(String, i64)>> {
+            SELECT task_id, CAST(AVG(duration_ms) AS INTEGER)
+            FROM (
+                SELECT
+                    task_id,
+                    duration_ms,
+                    ROW_NUMBER() OVER (PARTITION BY task_id ORDER BY start_time DESC) AS run_ix
+                FROM task_runs
+            )
+            WHERE run_ix <= ?
+            GROUP BY task_id
+        }
+    }
+
+    query! {
+        pub fn task_runs(task_id: String, limit: i64) -> Result<Vec<(String, i64, i64, Option<i32>)>> {
+            SELECT task_label, start_time, duration_ms, exit_code
+            FROM task_runs
+            WHERE task_id = ?
+            ORDER BY start_time DESC
+            LIMIT ?
+        }
+    }
+}
+
+impl Project {
+    /// Up to `limit` latest recorded runs of the given task, most recent first.
+    pub fn task_run_history(&self, task_id: &TaskId, limit: usize) -> Vec<TaskRunRecord> {
+        TASK_HISTORY_DB
+            .task_runs(task_id.0.clone(), limit as i64)
+            .log_err()
+            .unwrap_or_default()
+            .into_iter()
+            .map(
+                |(task_label, start_time, duration_ms, exit_code)| TaskRunRecord {
+                    task_id: task_id.clone(),
+                    task_label,
+                    start_time: UNIX_EPOCH + Duration::from_millis(start_time.max(0) as u64),
+                    duration: Duration::from_millis(duration_ms.max(0) as u64),
+                    exit_code,
+                },
+            )
+            .collect()
+    }
+
+    /// The average durations of the latest runs of every task that has ever been run,
+    /// loaded with a single query in the background.
+    pub fn average_task_run_durations(&self, cx: &AppContext) -> Task<HashMap<TaskId, Duration>> {
+        cx.background_executor().spawn(async move {
+            TASK_HISTORY_DB
+                .average_task_run_durations(AVERAGE_DURATION_RUNS as i64)
+                .await
+                .log_err()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(task_id, duration_ms)| {
+                    (
+                        TaskId(task_id),
+                        Duration::from_millis(duration_ms.max(0) as u64),
+                    )
+                })
+                .collect()
+        })
+    }
+
+    #[cfg_attr(not(feature = "terminal-integration"), allow(dead_code))]
+    pub(crate) fn record_task_run(&self, record: TaskRunRecord, cx: &AppContext) {
+        let start_time = record
+            .start_time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as i64;
+        cx.background_executor()
+            .spawn(async move {
+                TASK_HISTORY_DB
+                    .save_task_run(
+                        record.task_id.0.clone(),
+                        record.task_label,
+                        start_time,
+                        record.duration.as_millis() as i64,
+                        record.exit_code,
+                    )
+                    .await
+                    .log_err();
+                TASK_HISTORY_DB
+                    .prune_task_runs(record.task_id.0, KEPT_RUNS_PER_TASK as i64)
+                    .await
+                    .log_err();
+            })
+            .detach();
+    }
+}
@@ -1,44 +1,213 @@
-use crate::Project;
+use crate::{task_history::TaskRunRecord, Project};
 use anyhow::Context as _;
-use collections::HashMap;
-use gpui::{AnyWindowHandle, AppContext, Context, Entity, Model, ModelContext, WeakModel};
+use base64::Engine as _;
+use collections::{HashMap, HashSet};
+use fs::Fs;
+use futures::StreamExt as _;
+use gpui::{
+    AnyWindowHandle, AppContext, Context, Entity, EntityId, Model, ModelContext, SharedString,
+    Task, WeakModel,
+};
 use itertools::Itertools;
-use settings::{Settings, SettingsLocation};
+use remote::{RemoteShell, X11Forwarding};
+use serde::{Deserialize, Serialize};
+use settings::{Settings, SettingsLocation, SettingsStore};
 use smol::channel::bounded;
 use std::{
     borrow::Cow,
+    collections::VecDeque,
     env::{self},
+    future::Future,
     iter,
     path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
 };
-use task::{Shell, SpawnInTerminal};
+use task::{Shell, SpawnInTerminal, TaskId, VariableName, WorkingDirStrategy};
 use terminal::{
-    terminal_settings::{self, TerminalSettings},
-    TaskState, TaskStatus, Terminal, TerminalBuilder,
+    terminal_settings::{self, TerminalEvictionPolicy, TerminalSettings},
+    PtyBackend, TaskState, TaskStatus, Terminal, TerminalBuilder,
 };
+use tracing::Instrument as _;
 use util::ResultExt;
+use worktree::UpdatedEntriesSet;
 
 // #[cfg(target_os = "macos")]
 // use std::os::unix::ffi::OsStrExt;
 
 pub struct Terminals {
-    pub(crate) local_handles: Vec<WeakModel<terminal::Terminal>>,
+    pub(crate) local_handles: HashMap<EntityId, WeakModel<terminal::Terminal>>,
+    /// The latest outputs of the finished tasks, oldest first.
+    pub(crate) task_outputs: HashMap<TaskId, VecDeque<String>>,
+    pub(crate) env_hooks: Vec<TerminalEnvHook>,
+    /// The latest [`Project::active_project_directory`] reported with [`crate::Event::ProjectDirectoryChanged`].
+    pub(crate) last_project_directory: Option<Arc<Path>>,
+    pub(crate) project_directory_check: Option<Task<()>>,
+    /// The virtual environments found for the terminals' working directories, with the time they were found at.
+    pub(crate) venv_cache: HashMap<Arc<Path>, (PathBuf, Instant)>,
+    /// Whether any task was running, the last time the terminals were checked.
+    pub(crate) tasks_running: bool,
 }
 
-/// Terminals are opened either for the users shell, or to run a task.
+/// How long the active entry has to stay the same, before the change of its project directory is reported,
+/// so that rapid changes (e.g. selecting many files at once) produce a single event.
+const PROJECT_DIRECTORY_DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// Computes extra environment variables for a terminal that is about to be spawned,
+/// e.g. temporary credentials fetched over the network.
+/// The terminal is spawned once all hooks' tasks resolve, with their variables added
+/// on top of its environment, in the order of the hooks' registration.
+pub type TerminalEnvHook =
+    Box<dyn Fn(&TerminalKind, &AppContext) -> Task<HashMap<String, String>> + 'static>;
+
+/// Terminals are opened either for the users shell, a shell in a WSL distribution, or to run a task.
+/// Only the description of the terminal is serialized: the task's completion channels
+/// are created anew each time a terminal is created from it.
 #[allow(clippy::large_enum_variant)]
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TerminalKind {
     /// Run a shell at the given path (or $HOME if None)
     Shell(Option<PathBuf>),
     /// Run a task.
     Task(SpawnInTerminal),
+    /// Run a login shell in the given WSL distribution, at the given path (or its $HOME if None).
+    WslDistribution {
+        distro: String,
+        cwd: Option<PathBuf>,
+    },
+}
+
+/// The environment a terminal is spawned in, assembled by [`Project::assemble_terminal_env`],
+/// along with the project state it was assembled from, which [`Project::build_terminal`] spawns the terminal with.
+#[derive(Debug, Clone)]
+pub struct TerminalEnv {
+    pub kind: TerminalKind,
+    /// The working directory of the terminal.
+    pub path: Option<PathBuf>,
+    /// The terminal settings for the working directory.
+    pub settings: TerminalSettings,
+    /// The host, the command and the shell of the SSH connection the terminal runs over, for remote projects.
+    pub ssh_details: Option<(String, SshCommand, RemoteShell)>,
+    /// The variables to set in the terminal, on top of the inherited ones.
+    pub env: HashMap<String, String>,
+    /// The Python venv detected for the terminal's working directory, to activate in it.
+    pub python_venv_directory: Option<PathBuf>,
+    /// Whether the detected venv is a Conda environment.
+    pub is_conda_env: bool,
+    /// Whether `conda` is on the PATH, to activate the Conda environment with.
+    pub conda_available: bool,
+}
+
+/// The file system lookups the environment of a terminal depends on,
+/// done in the background before the environment is assembled.
+#[derive(Debug, Default)]
+struct TerminalEnvLookups {
+    /// The variables from the `terminal.env_files`, the later files overriding the earlier ones.
+    env_file_vars: HashMap<String, String>,
+    /// The toolchain channel pinned by the worktree's `rust-toolchain.toml`, for the tasks.
+    rust_toolchain: Option<String>,
+    /// The `bin` directories of the tools pinned with asdf for the local tasks, see [`asdf_toolchain_paths`].
+    asdf_toolchain_paths: Option<HashMap<String, PathBuf>>,
+    /// The existing directories listed in the `*.pth` files of a local task's venv, see [`venv_pth_paths`].
+    python_pth_paths: Vec<PathBuf>,
+    /// Whether the detected venv is a Conda environment, see [`is_conda_env`].
+    is_conda_env: bool,
+    /// Whether `conda` is on the PATH, looked up for the Conda environments only.
+    conda_available: bool,
+    /// The `PATH` entries of the local terminals that are files, skipped by [`add_environment_path`].
+    path_files: HashSet<PathBuf>,
+}
+
+impl TerminalKind {
+    /// The name of the kind, as recorded in the tracing spans.
+    fn name(&self) -> &'static str {
+        match self {
+            TerminalKind::Shell(_) => "shell",
+            TerminalKind::Task(_) => "task",
+            TerminalKind::WslDistribution { .. } => "wsl",
+        }
+    }
 }
 
 /// SshCommand describes how to connect to a remote server
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SshCommand {
     arguments: Vec<String>,
+    x11_forwarding: X11Forwarding,
+    /// The hosts to connect through, first hop first, each with its own arguments (e.g. credentials).
+    proxy_chain: Vec<SshCommand>,
+}
+
+impl SshCommand {
+    pub fn new(arguments: Vec<String>, x11_forwarding: X11Forwarding) -> Self {
+        Self {
+            arguments,
+            x11_forwarding,
+            proxy_chain: Vec::new(),
+        }
+    }
+
+    pub fn with_proxy_chain(mut self, proxy_chain: Vec<SshCommand>) -> Self {
+        self.proxy_chain = proxy_chain;
+        self
+    }
+
+    /// The `ProxyCommand` that reaches the host through the proxy chain, by forwarding the standard input and output
+    /// of an ssh to its last hop (`-W %h:%p`), which in turn connects through the hops before it the same way.
+    fn proxy_command(proxy_chain: &[SshCommand]) -> Option<String> {
+        let (last_hop, previous_hops) = proxy_chain.split_last()?;
+        let mut command = vec!["ssh".to_string()];
+        if let Some(previous_hops_command) = Self::proxy_command(previous_hops) {
+            // Escaped, so that the `%h:%p` tokens are expanded by the ssh connecting to the last hop, not this one.
+            command.push("-o".to_string());
+            command.push(format!(
+                "ProxyCommand={}",
+                previous_hops_command.replace('%', "%%")
+            ));
+        }
+        command.extend(last_hop.arguments.iter().cloned());
+        command.push("-W".to_string());
+        command.push("%h:%p".to_string());
+        shlex::try_join(command.iter().map(String::as_str)).ok()
+    }
+
+    /// Checks that the arguments do not conflict with the pseudo-terminal (`-t`)
+    /// and the remote command, which [`wrap_for_ssh`] appends to them.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let mut arguments = self.arguments.iter().map(String::as_str).peekable();
+        while let Some(argument) = arguments.next() {
+            match argument {
+                "-T" => anyhow::bail!(
+                    "ssh arguments disable the pseudo-terminal allocation (-T), which terminals require"
+                ),
+                "-N" => anyhow::bail!(
+                    "ssh arguments disable the remote command execution (-N), which terminals require"
+                ),
+                _ => {}
+            }
+            let option = match argument {
+                "-o" => arguments.peek().copied(),
+                _ => argument.strip_prefix("-o"),
+            };
+            if option.is_some_and(disables_tty) {
+                anyhow::bail!(
+                    "ssh arguments disable the pseudo-terminal allocation (RequestTTY), which terminals require"
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether an ssh `-o` option is `RequestTTY=no` (or `RequestTTY no`).
+fn disables_tty(option: &str) -> bool {
+    let mut parts = option.splitn(2, |c: char| c == '=' || c.is_whitespace());
+    parts
+        .next()
+        .is_some_and(|key| key.eq_ignore_ascii_case("RequestTTY"))
+        && parts
+            .next()
+            .is_some_and(|value| value.trim().eq_ignore_ascii_case("no"))
 }
 
 impl Project {
@@ -54,27 +223,56 @@ impl Project {
                     .root_entry()
                     .filter(|entry| entry.is_dir())
                     .map(|_| worktree.abs_path().to_path_buf())
+                    .filter(|path| !is_in_git_directory(path))
             });
         worktree
     }
 
-    pub fn first_project_directory(&self, cx: &AppContext) -> Option<PathBuf> {
-        let worktree = self.worktrees(cx).next()?;
-        let worktree = worktree.read(cx);
-        if worktree.root_entry()?.is_dir() {
-            Some(worktree.abs_path().to_path_buf())
-        } else {
-            None
-        }
+    /// Emits [`crate::Event::ProjectDirectoryChanged`] once the active entry settles,
+    /// if [`Project::active_project_directory`] differs from the last reported one.
+    pub(crate) fn schedule_project_directory_check(&mut self, cx: &mut ModelContext<Self>) {
+        self.terminals.project_directory_check = Some(cx.spawn(|project, mut cx| async move {
+            cx.background_executor()
+                .timer(PROJECT_DIRECTORY_DEBOUNCE)
+                .await;
+            project
+                .update(&mut cx, |project, cx| {
+                    let path = project.active_project_directory(cx).map(Arc::from);
+                    if path != project.terminals.last_project_directory {
+                        project.terminals.last_project_directory = path.clone();
+                        cx.emit(crate::Event::ProjectDirectoryChanged { path });
+                    }
+                })
+                .ok();
+        }));
+    }
+
+    /// The directory new terminals should open in, in a project with possibly many worktrees:
+    /// the root of the worktree containing the active entry (usually the active editor's buffer),
+    /// then the root of any other directory worktree, and, if the project only has single-file
+    /// worktrees, the directory containing the first one.
+    pub fn default_project_directory(&self, cx: &AppContext) -> Option<PathBuf> {
+        self.active_project_directory(cx).or_else(|| {
+            self.worktrees(cx).find_map(|worktree| {
+                let worktree = worktree.read(cx);
+                worktree
+                    .abs_path()
+                    .parent()
+                    .filter(|path| !is_in_git_directory(path))
+                    .map(Path::to_path_buf)
+            })
+        })
     }
 
-    fn ssh_details(&self, cx: &AppContext) -> Option<(String, SshCommand)> {
+    fn ssh_details(&self, cx: &AppContext) -> Option<(String, SshCommand, RemoteShell)> {
         if let Some(ssh_client) = &self.ssh_client {
             let ssh_client = ssh_client.read(cx);
             if let Some(args) = ssh_client.ssh_args() {
+                let connection_options = ssh_client.connection_options();
                 return Some((
-                    ssh_client.connection_options().host.clone(),
-                    SshCommand { arguments: args },
+                    connection_options.host.clone(),
+                    SshCommand::new(args, connection_options.x11_forwarding),
+                    connection_options.remote_shell,
                 ));
             }
         }
@@ -82,78 +280,308 @@ impl Project {
         return None;
     }
 
+    /// The host of the ssh connection, which the project's terminals run over,
+    /// and the connection's latest round-trip time in milliseconds, if measured yet.
+    pub fn ssh_connection_rtt(&self, cx: &AppContext) -> Option<(String, Option<u64>)> {
+        let ssh_client = self.ssh_client.as_ref()?.read(cx);
+        Some((ssh_client.connection_options().host, ssh_client.rtt_ms()))
+    }
+
+    pub fn register_terminal_env_hook(
+        &mut self,
+        hook: impl Fn(&TerminalKind, &AppContext) -> Task<HashMap<String, String>> + 'static,
+    ) {
+        self.terminals.env_hooks.push(Box::new(hook));
+    }
+
+    fn env_hook_tasks(
+        &self,
+        kind: &TerminalKind,
+        cx: &AppContext,
+    ) -> Vec<Task<HashMap<String, String>>> {
+        self.terminals
+            .env_hooks
+            .iter()
+            .map(|hook| hook(kind, cx))
+            .collect()
+    }
+
+    /// Creates a terminal of the given kind, once all registered [`TerminalEnvHook`]s have resolved.
+    /// When `display_name` is given, it is shown instead of the title derived from the shell or the task.
     pub fn create_terminal(
         &mut self,
         kind: TerminalKind,
+        display_name: Option<String>,
         window: AnyWindowHandle,
         cx: &mut ModelContext<Self>,
-    ) -> anyhow::Result<Model<Terminal>> {
-        let path = match &kind {
-            TerminalKind::Shell(path) => path.as_ref().map(|path| path.to_path_buf()),
-            TerminalKind::Task(spawn_task) => {
-                if let Some(cwd) = &spawn_task.cwd {
-                    Some(cwd.clone())
-                } else {
-                    self.active_project_directory(cx)
+    ) -> Task<anyhow::Result<Model<Terminal>>> {
+        if self.environment.read(cx).environment_files_changed() {
+            self.environment
+                .update(cx, |environment, _| environment.invalidate_cache());
+        }
+        let mut kind = kind;
+        if let TerminalKind::Task(spawn_task) = &mut kind {
+            if let Some(cwd) = self.task_working_directory(spawn_task, cx) {
+                spawn_task.cwd = Some(cwd);
+            }
+            self.report_task_launched(spawn_task, cx);
+        }
+        let path = self.terminal_path(&kind, cx);
+        let worktree_id = path
+            .as_deref()
+            .and_then(|path| self.find_worktree(path, cx))
+            .map(|(worktree, _)| worktree.read(cx).id().to_usize());
+        let span = tracing::info_span!("create_terminal", kind = kind.name(), worktree_id);
+        let settings = self.terminal_settings(path.as_deref(), cx).clone();
+        let terminal_env = self.assemble_terminal_env(&kind, &settings, cx);
+        cx.spawn(|project, mut cx| {
+            async move {
+                let terminal_env = terminal_env.await?;
+                project.update(&mut cx, |project, cx| {
+                    project.build_terminal(terminal_env, display_name, window, cx)
+                })?
+            }
+            .instrument(span)
+        })
+    }
+
+    /// Assembles the environment of a terminal of the given kind, without spawning the terminal:
+    /// the variables from the settings, the env files and the task, the detected Python venv
+    /// and the ones from the registered [`TerminalEnvHook`]s.
+    /// The SSH connection and the working directory are resolved once, up front, and returned
+    /// with the settings in the [`TerminalEnv`], so the terminal is spawned with the same state.
+    pub fn assemble_terminal_env(
+        &self,
+        kind: &TerminalKind,
+        settings: &TerminalSettings,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<anyhow::Result<TerminalEnv>> {
+        let kind = kind.clone();
+        let settings = settings.clone();
+        let path = self.terminal_path(&kind, cx);
+        let ssh_details = self.ssh_details(cx);
+        // The venv of an SSH terminal is on the remote host, where the remote shell's own setup activates it.
+        let python_venv_directory = if ssh_details.is_some() {
+            Task::ready(None)
+        } else {
+            self.python_venv_directory(path.as_deref(), &settings, cx)
+        };
+        let env_hook_tasks = self.env_hook_tasks(&kind, cx);
+        let lookups =
+            self.terminal_env_lookups(&kind, path.as_deref(), &settings, ssh_details.is_none(), cx);
+        let fs = self.fs.clone();
+        cx.spawn(|project, mut cx| async move {
+            // Resolve everything asynchronous up front, so that the environment is assembled
+            // within a single update, from the same project state.
+            let python_venv_directory = python_venv_directory
+                .instrument(tracing::info_span!("venv_detection"))
+                .await;
+            let mut lookups = lookups.instrument(tracing::info_span!("env_lookups")).await;
+            if let Some(venv_path) = python_venv_directory.as_deref() {
+                if matches!(kind, TerminalKind::Task(_)) {
+                    lookups.python_pth_paths = venv_pth_paths(fs.as_ref(), venv_path)
+                        .await
+                        .log_err()
+                        .unwrap_or_default();
+                }
+                lookups.is_conda_env = is_conda_env(fs.as_ref(), venv_path).await;
+                if lookups.is_conda_env {
+                    lookups.conda_available = cx
+                        .background_executor()
+                        .spawn(async { which::which("conda").is_ok() })
+                        .await;
+                }
+            }
+            let mut hooks_env = HashMap::default();
+            async {
+                for hook_env in env_hook_tasks {
+                    hooks_env.extend(hook_env.await);
                 }
             }
+            .instrument(tracing::info_span!("env_hooks"))
+            .await;
+            project.update(&mut cx, |project, cx| {
+                let _env_assembly = tracing::info_span!("env_assembly").entered();
+                let is_conda_env = lookups.is_conda_env;
+                let conda_available = lookups.conda_available;
+                let mut env = project.terminal_env(
+                    &kind,
+                    &settings,
+                    lookups,
+                    python_venv_directory.as_deref(),
+                    ssh_details.is_some(),
+                    cx,
+                );
+                env.extend(hooks_env);
+                TerminalEnv {
+                    kind,
+                    path,
+                    settings,
+                    ssh_details,
+                    env,
+                    python_venv_directory,
+                    is_conda_env,
+                    conda_available,
+                }
+            })
+        })
+    }
+
+    /// Spawns the task of the given terminal again, in a new terminal, as it was originally defined.
+    pub fn rerun_terminal(
+        &mut self,
+        terminal: &Model<Terminal>,
+        window: AnyWindowHandle,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<anyhow::Result<Model<Terminal>>> {
+        let Some(task) = terminal.read(cx).task() else {
+            return Task::ready(Err(anyhow::anyhow!(
+                "The terminal does not run a task to rerun"
+            )));
         };
-        let ssh_details = self.ssh_details(cx);
+        let kind = TerminalKind::Task(task.spawned_task.clone());
+        self.create_terminal(kind, None, window, cx)
+    }
 
-        let mut settings_location = None;
-        if let Some(path) = path.as_ref() {
-            if let Some((worktree, _)) = self.find_worktree(path, cx) {
-                settings_location = Some(SettingsLocation {
-                    worktree_id: worktree.read(cx).id(),
-                    path,
-                });
+    /// The directory the task's [`WorkingDirStrategy`] picks, if the task has one and it resolves to a directory.
+    fn task_working_directory(
+        &self,
+        spawn_task: &SpawnInTerminal,
+        cx: &AppContext,
+    ) -> Option<PathBuf> {
+        let variable =
+            |name: VariableName| spawn_task.env.get(&name.to_string()).map(PathBuf::from);
+        match spawn_task.working_dir_strategy.as_ref()? {
+            WorkingDirStrategy::WorkspaceRoot => variable(VariableName::WorktreeRoot),
+            WorkingDirStrategy::Explicit(path) => Some(path.clone()),
+            WorkingDirStrategy::CurrentFileDir => {
+                Some(variable(VariableName::File)?.parent()?.to_path_buf())
+            }
+            WorkingDirStrategy::NearestAncestorWithFile(file_name) => {
+                let file = variable(VariableName::File)?;
+                let (worktree, _) = self.find_worktree(&file, cx)?;
+                let worktree = worktree.read(cx);
+                let root = worktree.abs_path();
+                file.ancestors()
+                    .skip(1)
+                    .take_while(|dir| dir.starts_with(&root))
+                    .find(|dir| {
+                        dir.strip_prefix(&root)
+                            .ok()
+                            .and_then(|relative_dir| {
+                                worktree.entry_for_path(relative_dir.join(file_name))
+                            })
+                            .is_some_and(|entry| entry.is_file())
+                    })
+                    .map(Path::to_path_buf)
             }
         }
-        let settings = TerminalSettings::get(settings_location, cx);
+    }
 
-        let (completion_tx, completion_rx) = bounded(1);
+    /// Reports the task launch to the telemetry, with the language of the file the task was launched for.
+    fn report_task_launched(&self, spawn_task: &SpawnInTerminal, cx: &AppContext) {
+        let telemetry = self.client().telemetry().clone();
+        let languages = self.languages().clone();
+        let is_remote = self.is_via_ssh();
+        let file = spawn_task
+            .env
+            .get(&VariableName::File.to_string())
+            .map(PathBuf::from);
+        cx.background_executor()
+            .spawn(async move {
+                let language = match file {
+                    Some(file) => languages
+                        .language_for_file_path(&file)
+                        .await
+                        .ok()
+                        .map(|language| language.name().to_string()),
+                    None => None,
+                };
+                telemetry.report_task_event(language, is_remote);
+            })
+            .detach();
+    }
 
-        // Start with the environment that we might have inherited from the Zed CLI.
-        let mut env = self
-            .environment
-            .read(cx)
-            .get_cli_environment()
-            .unwrap_or_default();
-        // Then extend it with the explicit env variables from the settings, so they take
-        // precedence.
-        env.extend(settings.env.clone());
+    /// Spawns the terminal in the environment assembled by [`Project::assemble_terminal_env`]:
+    /// wraps its shell or task for SSH or WSL, if needed, and starts it in a new pseudoterminal.
+    /// The settings, the SSH connection and the working directory are taken from the [`TerminalEnv`],
+    /// not read again, so they cannot change between the environment assembly and the spawn.
+    pub fn build_terminal(
+        &mut self,
+        terminal_env: TerminalEnv,
+        display_name: Option<String>,
+        window: AnyWindowHandle,
+        cx: &mut ModelContext<Self>,
+    ) -> anyhow::Result<Model<Terminal>> {
+        let TerminalEnv {
+            kind,
+            path,
+            settings,
+            ssh_details,
+            mut env,
+            python_venv_directory,
+            is_conda_env,
+            conda_available,
+        } = terminal_env;
+        let terminals_to_evict =
+            self.terminals_to_evict(settings.max_terminals, settings.eviction_policy, cx)?;
+
+        let (completion_tx, completion_rx) = bounded(1);
 
         let local_path = if ssh_details.is_none() {
             path.clone()
         } else {
             None
         };
-        let python_venv_directory = path
-            .as_ref()
-            .and_then(|path| self.python_venv_directory(path, settings, cx));
         let mut python_venv_activate_command = None;
+        let max_scroll_history_lines = match &kind {
+            // Tasks like `cargo build --all` may produce a lot of output, ergo allow maximum scrolling by default.
+            // After the task finishes, we do not allow appending to that terminal, so small tasks output should not
+            // cause excessive memory usage over time.
+            TerminalKind::Task(spawn_task) => spawn_task
+                .override_max_scroll_history_lines
+                .unwrap_or(terminal::MAX_SCROLL_HISTORY_LINES),
+            _ => settings.scroll_history_lines(),
+        };
 
         let (spawn_task, shell) = match kind {
             TerminalKind::Shell(_) => {
                 if let Some(python_venv_directory) = python_venv_directory {
-                    python_venv_activate_command =
-                        self.python_activate_command(&python_venv_directory, settings);
+                    python_venv_activate_command = if is_conda_env {
+                        // Conda environments come without the venv activation scripts.
+                        // Without `conda` on the PATH, the environment is injected by `terminal_env` instead.
+                        conda_available.then(|| {
+                            format!(
+                                "conda activate {}\n",
+                                single_quote(&python_venv_directory.to_string_lossy())
+                            )
+                        })
+                    } else {
+                        self.python_activate_command(&python_venv_directory, cx)
+                    };
                 }
 
                 match &ssh_details {
-                    Some((host, ssh_command)) => {
+                    Some((host, ssh_command, remote_shell)) => {
                         log::debug!("Connecting to a remote server: {ssh_command:?}");
-
-                        // Alacritty sets its terminfo to `alacritty`, this requiring hosts to have it installed
-                        // to properly display colors.
-                        // We do not have the luxury of assuming the host has it installed,
-                        // so we set it to a default that does not break the highlighting via ssh.
-                        env.entry("TERM".to_string())
-                            .or_insert_with(|| "xterm-256color".to_string());
-
-                        let (program, args) =
-                            wrap_for_ssh(ssh_command, None, path.as_deref(), env, None);
-                        env = HashMap::default();
+                        let forward_env_keys = settings
+                            .ssh_forward_env
+                            .iter()
+                            .map(String::as_str)
+                            .collect::<Vec<_>>();
+                        let (ssh_env, remote_env) =
+                            split_ssh_connection_env(env, &forward_env_keys);
+                        let (program, args) = wrap_for_ssh(
+                            ssh_command,
+                            None,
+                            Some(&settings.shell),
+                            path.as_deref(),
+                            remote_env,
+                            &forward_env_keys,
+                            None,
+                            *remote_shell,
+                        )?;
+                        env = ssh_env;
                         (
                             None,
                             Shell::WithArguments {
@@ -166,41 +594,84 @@ impl Project {
                     None => (None, settings.shell.clone()),
                 }
             }
+            TerminalKind::WslDistribution { distro, cwd } => {
+                anyhow::ensure!(
+                    ssh_details.is_none(),
+                    "WSL terminals can only be opened in local projects"
+                );
+                // The venv was found through its Windows path, the shell inside WSL needs the mounted one.
+                python_venv_activate_command = python_venv_directory
+                    .as_deref()
+                    .and_then(wsl_path)
+                    .and_then(|venv_directory| {
+                        activate_script_command(
+                            terminal_settings::ActivateScript::Default,
+                            &format!("{venv_directory}/bin/activate"),
+                        )
+                    });
+                let (program, args) = wrap_for_wsl(&distro, cwd.as_deref());
+                (
+                    None,
+                    Shell::WithArguments {
+                        program,
+                        args,
+                        title_override: Some(format!("{distro} — WSL").into()),
+                    },
+                )
+            }
             TerminalKind::Task(spawn_task) => {
+                let spawned_task = spawn_task.clone();
+                let cwd_label = spawn_task.cwd_label.or_else(|| {
+                    spawn_task
+                        .cwd
+                        .as_ref()
+                        .map(|cwd| cwd.to_string_lossy().to_string())
+                });
                 let task_state = Some(TaskState {
                     id: spawn_task.id,
                     full_label: spawn_task.full_label,
                     label: spawn_task.label,
                     command_label: spawn_task.command_label,
+                    cwd_label,
+                    cwd: path.clone(),
                     hide: spawn_task.hide,
                     status: TaskStatus::Running,
-                    show_summary: spawn_task.show_summary,
-                    show_command: spawn_task.show_command,
+                    // Extra lines around a TUI program's output would break its full-screen layout.
+                    show_summary: spawn_task.show_summary && !spawn_task.interactive_tui,
+                    show_command: spawn_task.show_command && !spawn_task.interactive_tui,
+                    max_output_lines: spawn_task.max_output_lines,
+                    output_truncated: false,
+                    group_id: spawn_task.group_id,
+                    test_framework: spawn_task.test_framework,
+                    exit_code: None,
+                    restart_on_crash: spawn_task.restart_on_crash,
+                    crash_count: 0,
+                    started_at: Instant::now(),
+                    spawned_task,
                     completion_rx,
                 });
 
-                env.extend(spawn_task.env);
-
-                if let Some(venv_path) = &python_venv_directory {
-                    env.insert(
-                        "VIRTUAL_ENV".to_string(),
-                        venv_path.to_string_lossy().to_string(),
-                    );
-                }
-
                 match &ssh_details {
-                    Some((host, ssh_command)) => {
+                    Some((host, ssh_command, remote_shell)) => {
                         log::debug!("Connecting to a remote server: {ssh_command:?}");
-                        env.entry("TERM".to_string())
-                            .or_insert_with(|| "xterm-256color".to_string());
+                        let forward_env_keys = settings
+                            .ssh_forward_env
+                            .iter()
+                            .map(String::as_str)
+                            .collect::<Vec<_>>();
+                        let (ssh_env, remote_env) =
+                            split_ssh_connection_env(env, &forward_env_keys);
                         let (program, args) = wrap_for_ssh(
                             ssh_command,
                             Some((&spawn_task.command, &spawn_task.args)),
+                            None,
                             path.as_deref(),
-                            env,
-                            python_venv_directory,
-                        );
-                        env = HashMap::default();
+                            remote_env,
+                            &forward_env_keys,
+                            python_venv_directory.filter(|_| !spawn_task.interactive_tui),
+                            *remote_shell,
+                        )?;
+                        env = ssh_env;
                         (
                             task_state,
                             Shell::WithArguments {
@@ -210,243 +681,1183 @@ impl Project {
                             },
                         )
                     }
-                    None => {
-                        if let Some(venv_path) = &python_venv_directory {
-                            add_environment_path(&mut env, &venv_path.join("bin")).log_err();
-                        }
-
-                        (
-                            task_state,
-                            Shell::WithArguments {
-                                program: spawn_task.command,
-                                args: spawn_task.args,
-                                title_override: None,
-                            },
-                        )
-                    }
+                    None => (
+                        task_state,
+                        Shell::WithArguments {
+                            program: spawn_task.command,
+                            args: spawn_task.args,
+                            title_override: None,
+                        },
+                    ),
                 }
             }
         };
 
-        let terminal = TerminalBuilder::new(
-            local_path,
-            spawn_task,
-            shell,
-            env,
-            settings.cursor_shape.unwrap_or_default(),
-            settings.alternate_scroll,
-            settings.max_scroll_history_lines,
-            ssh_details.is_some(),
-            window,
-            completion_tx,
-            cx,
-        )
-        .map(|builder| {
-            let terminal_handle = cx.new_model(|cx| builder.subscribe(cx));
+        let task_id_and_label = spawn_task
+            .as_ref()
+            .map(|task| (task.id.clone(), task.full_label.clone()));
+        let cursor_shape = settings.cursor_shape_for(spawn_task.is_some());
+        let alternate_scroll = settings.alternate_scroll_for(spawn_task.is_some());
+        let builder = tracing::info_span!("pty_spawn").in_scope(|| {
+            TerminalBuilder::new(
+                local_path,
+                spawn_task,
+                shell,
+                env,
+                cursor_shape,
+                alternate_scroll,
+                max_scroll_history_lines,
+                ssh_details.is_some(),
+                window,
+                completion_tx,
+                PtyBackend::Real,
+                cx,
+            )
+        });
+        let terminal = builder.map(|builder| {
+            self.evict_terminals(terminals_to_evict, cx);
+            let terminal_handle = cx.new_model(|cx| {
+                let mut terminal = builder.subscribe(cx);
+                terminal.set_display_name(display_name.map(SharedString::from));
+                terminal
+            });
 
             self.terminals
                 .local_handles
-                .push(terminal_handle.downgrade());
+                .insert(terminal_handle.entity_id(), terminal_handle.downgrade());
+
+            let weak_terminal = terminal_handle.downgrade();
+            let settings_path = path.clone();
+            let settings_subscription = cx.observe_global::<SettingsStore>(move |project, cx| {
+                let path = settings_path.as_deref();
+                let worktree_id = path
+                    .and_then(|path| project.find_worktree(path, cx))
+                    .map(|(worktree, _)| worktree.read(cx).id());
+                weak_terminal
+                    .update(cx, |terminal, cx| {
+                        let settings_location = path
+                            .zip(worktree_id)
+                            .map(|(path, worktree_id)| SettingsLocation { worktree_id, path });
+                        terminal.update_settings(TerminalSettings::get(settings_location, cx));
+                    })
+                    .ok();
+            });
 
             let id = terminal_handle.entity_id();
             cx.observe_release(&terminal_handle, move |project, _terminal, cx| {
-                let handles = &mut project.terminals.local_handles;
-
-                if let Some(index) = handles
-                    .iter()
-                    .position(|terminal| terminal.entity_id() == id)
-                {
-                    handles.remove(index);
+                drop(settings_subscription);
+                if project.terminals.local_handles.remove(&id).is_some() {
                     cx.notify();
                 }
+                project.update_tasks_running(cx);
             })
             .detach();
 
+            if let Some((task_id, task_label)) = task_id_and_label {
+                let start_time = SystemTime::now();
+                cx.subscribe(
+                    &terminal_handle,
+                    move |project, terminal, event, cx| match event {
+                        terminal::Event::TaskFinished => {
+                            let terminal = terminal.read(cx);
+                            project.record_task_run(
+                                TaskRunRecord {
+                                    task_id: task_id.clone(),
+                                    task_label: task_label.clone(),
+                                    start_time,
+                                    duration: start_time.elapsed().unwrap_or_default(),
+                                    exit_code: terminal.task().and_then(|task| task.exit_code),
+                                },
+                                cx,
+                            );
+                            let output = terminal.output_text();
+                            project.record_task_output(task_id.clone(), output, cx);
+                            project.update_tasks_running(cx);
+                        }
+                        terminal::Event::TestResult(result) => {
+                            cx.emit(crate::Event::TaskTestResult {
+                                task_id: task_id.clone(),
+                                result: result.clone(),
+                            });
+                        }
+                        _ => {}
+                    },
+                )
+                .detach();
+                self.update_tasks_running(cx);
+            }
+
             if let Some(activate_command) = python_venv_activate_command {
                 self.activate_python_virtual_environment(activate_command, &terminal_handle, cx);
             }
             terminal_handle
         });
 
-        terminal
+        terminal.map_err(anyhow::Error::from)
     }
 
-    pub fn python_venv_directory(
+    /// Picks the terminals to close according to the eviction policy, so that a new one fits
+    /// into `max_terminals`. Terminals that the policy does not allow to close are kept open.
+    fn terminals_to_evict(
         &self,
-        abs_path: &Path,
-        settings: &TerminalSettings,
+        max_terminals: Option<usize>,
+        eviction_policy: TerminalEvictionPolicy,
         cx: &AppContext,
-    ) -> Option<PathBuf> {
-        let venv_settings = settings.detect_venv.as_option()?;
-        if let Some(path) = self.find_venv_in_worktree(abs_path, &venv_settings, cx) {
-            return Some(path);
+    ) -> anyhow::Result<Vec<Model<Terminal>>> {
+        let Some(max_terminals) = max_terminals else {
+            return Ok(Vec::new());
+        };
+        let open_terminals = self
+            .terminals
+            .local_handles
+            .values()
+            .filter_map(|handle| handle.upgrade())
+            .collect::<Vec<_>>();
+        let excess = (open_terminals.len() + 1).saturating_sub(max_terminals);
+        if excess == 0 {
+            return Ok(Vec::new());
+        }
+        let mut candidates = match eviction_policy {
+            TerminalEvictionPolicy::Error => {
+                anyhow::bail!("Cannot open more than {max_terminals} terminals")
+            }
+            TerminalEvictionPolicy::OldestAny => open_terminals,
+            TerminalEvictionPolicy::OldestCompleted => open_terminals
+                .into_iter()
+                .filter(|terminal| {
+                    terminal
+                        .read(cx)
+                        .task()
+                        .is_some_and(|task| task.status != TaskStatus::Running)
+                })
+                .collect(),
+        };
+        candidates.sort_by_key(|terminal| terminal.read(cx).created_at());
+        candidates.truncate(excess);
+        Ok(candidates)
+    }
+
+    /// Closes the terminals picked by [`Project::terminals_to_evict`].
+    fn evict_terminals(&mut self, terminals: Vec<Model<Terminal>>, cx: &mut ModelContext<Self>) {
+        self.terminals
+            .local_handles
+            .retain(|_, handle| handle.upgrade().is_some());
+        for terminal in terminals {
+            self.terminals.local_handles.remove(&terminal.entity_id());
+            terminal.update(cx, |_, cx| cx.emit(terminal::Event::CloseTerminal));
+            cx.emit(crate::Event::TerminalEvicted {
+                entity_id: terminal.entity_id(),
+            });
         }
-        self.find_venv_on_filesystem(abs_path, &venv_settings, cx)
     }
 
-    fn find_venv_in_worktree(
+    /// Resolves the environment a new terminal of the given kind would be spawned with,
+    /// without spawning it: the same steps as [`Project::create_terminal`] perform,
+    /// including the registered [`TerminalEnvHook`]s. Fails if the environment cannot be assembled.
+    pub fn resolve_terminal_env(
         &self,
-        abs_path: &Path,
-        venv_settings: &terminal_settings::VenvSettingsContent,
-        cx: &AppContext,
-    ) -> Option<PathBuf> {
-        let bin_dir_name = match std::env::consts::OS {
-            "windows" => "Scripts",
-            _ => "bin",
-        };
-        venv_settings
-            .directories
-            .iter()
-            .map(|name| abs_path.join(name))
-            .find(|venv_path| {
-                let bin_path = venv_path.join(bin_dir_name);
-                self.find_worktree(&bin_path, cx)
-                    .and_then(|(worktree, relative_path)| {
-                        worktree.read(cx).entry_for_path(&relative_path)
-                    })
-                    .is_some_and(|entry| entry.is_dir())
-            })
+        kind: TerminalKind,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<anyhow::Result<HashMap<String, String>>> {
+        let path = self.terminal_path(&kind, cx);
+        let settings = self.terminal_settings(path.as_deref(), cx).clone();
+        let terminal_env = self.assemble_terminal_env(&kind, &settings, cx);
+        cx.background_executor()
+            .spawn(async move { terminal_env.await.map(|terminal_env| terminal_env.env) })
+    }
+
+    fn terminal_path(&self, kind: &TerminalKind, cx: &AppContext) -> Option<PathBuf> {
+        match kind {
+            TerminalKind::Shell(path) => path.as_ref().map(|path| path.to_path_buf()),
+            TerminalKind::Task(spawn_task) => {
+                if let Some(cwd) = &spawn_task.cwd {
+                    Some(cwd.clone())
+                } else {
+                    self.active_project_directory(cx)
+                }
+            }
+            TerminalKind::WslDistribution { cwd, .. } => cwd.clone(),
+        }
+    }
+
+    fn terminal_settings<'a>(
+        &'a self,
+        path: Option<&'a Path>,
+        cx: &'a AppContext,
+    ) -> &'a TerminalSettings {
+        let mut settings_location = None;
+        if let Some(path) = path {
+            if let Some((worktree, _)) = self.find_worktree(path, cx) {
+                settings_location = Some(SettingsLocation {
+                    worktree_id: worktree.read(cx).id(),
+                    path,
+                });
+            }
+        }
+        TerminalSettings::get(settings_location, cx)
     }
 
-    fn find_venv_on_filesystem(
+    /// Starts the file system lookups for the environment of a terminal at the given path,
+    /// to be awaited off the main thread.
+    fn terminal_env_lookups(
         &self,
-        abs_path: &Path,
-        venv_settings: &terminal_settings::VenvSettingsContent,
+        kind: &TerminalKind,
+        path: Option<&Path>,
+        settings: &TerminalSettings,
+        is_local: bool,
         cx: &AppContext,
-    ) -> Option<PathBuf> {
-        let (worktree, _) = self.find_worktree(abs_path, cx)?;
-        let fs = worktree.read(cx).as_local()?.fs();
-        let bin_dir_name = match std::env::consts::OS {
-            "windows" => "Scripts",
-            _ => "bin",
-        };
-        venv_settings
-            .directories
+    ) -> impl Future<Output = TerminalEnvLookups> {
+        let is_task = matches!(kind, TerminalKind::Task(_));
+        let local_task_path = path.filter(|_| is_task && is_local).map(Path::to_path_buf);
+        let fs = self.fs.clone();
+        let worktree_root = path
+            .and_then(|path| self.find_worktree(path, cx))
+            .map(|(worktree, _)| worktree.read(cx).abs_path());
+        // Any of these may end up as the `PATH` the venv and toolchain directories are prepended to.
+        let mut path_values = Vec::new();
+        if is_local {
+            let cli_environment = self.environment.read(cx).get_cli_environment();
+            path_values.extend(cli_environment.as_ref().and_then(env_path).cloned());
+            path_values.extend(env_path(&settings.env).cloned());
+            if let TerminalKind::Task(spawn_task) = kind {
+                path_values.extend(env_path(&spawn_task.env).cloned());
+            }
+            path_values.extend(env::var("PATH").ok());
+        }
+        // Only the local file system is read, so the env files are not loaded for the remote projects.
+        let env_files = settings
+            .env_files
             .iter()
-            .map(|name| abs_path.join(name))
-            .find(|venv_path| {
-                let bin_path = venv_path.join(bin_dir_name);
-                // One-time synchronous check is acceptable for terminal/task initialization
-                smol::block_on(fs.metadata(&bin_path))
-                    .ok()
-                    .flatten()
-                    .map_or(false, |meta| meta.is_dir)
+            .filter(|_| is_local)
+            .map(|env_file| match &worktree_root {
+                Some(worktree_root) => worktree_root.join(env_file),
+                None => env_file.clone(),
             })
+            .collect::<Vec<_>>();
+        async move {
+            let mut lookups = TerminalEnvLookups::default();
+            for env_file in env_files {
+                if let Ok(contents) = fs.load(&env_file).await {
+                    lookups.env_file_vars.extend(parse_env_file(&contents));
+                }
+            }
+            async {
+                // The worktree of a remote project is on the remote host, not on the local file system.
+                if let Some(worktree_root) = worktree_root.filter(|_| is_task && is_local) {
+                    lookups.rust_toolchain = fs
+                        .load(&worktree_root.join("rust-toolchain.toml"))
+                        .await
+                        .ok()
+                        .and_then(|contents| rust_toolchain_channel(&contents));
+                }
+                if let Some(path) = local_task_path {
+                    lookups.asdf_toolchain_paths = asdf_toolchain_paths(fs.as_ref(), &path).await;
+                }
+            }
+            .instrument(tracing::info_span!("toolchain_lookup"))
+            .await;
+            if is_local {
+                path_values.extend(env_path(&lookups.env_file_vars).cloned());
+                lookups.path_files = path_files(fs.as_ref(), &path_values).await;
+            }
+            lookups
+        }
     }
 
-    fn python_activate_command(
+    fn terminal_env(
         &self,
-        venv_base_directory: &Path,
+        kind: &TerminalKind,
         settings: &TerminalSettings,
-    ) -> Option<String> {
-        let venv_settings = settings.detect_venv.as_option()?;
-        let activate_keyword = match venv_settings.activate_script {
-            terminal_settings::ActivateScript::Default => match std::env::consts::OS {
-                "windows" => ".",
-                _ => "source",
-            },
-            terminal_settings::ActivateScript::Nushell => "overlay use",
-            terminal_settings::ActivateScript::PowerShell => ".",
-            _ => "source",
-        };
-        let activate_script_name = match venv_settings.activate_script {
-            terminal_settings::ActivateScript::Default => "activate",
-            terminal_settings::ActivateScript::Csh => "activate.csh",
-            terminal_settings::ActivateScript::Fish => "activate.fish",
-            terminal_settings::ActivateScript::Nushell => "activate.nu",
-            terminal_settings::ActivateScript::PowerShell => "activate.ps1",
-        };
-        let path = venv_base_directory
-            .join(match std::env::consts::OS {
-                "windows" => "Scripts",
-                _ => "bin",
-            })
-            .join(activate_script_name)
-            .to_string_lossy()
-            .to_string();
-        let quoted = shlex::try_quote(&path).ok()?;
-        let line_ending = match std::env::consts::OS {
-            "windows" => "\r",
-            _ => "\n",
-        };
-        Some(format!("{} {}{}", activate_keyword, quoted, line_ending))
-    }
+        lookups: TerminalEnvLookups,
+        python_venv_directory: Option<&Path>,
+        is_ssh_terminal: bool,
+        cx: &AppContext,
+    ) -> HashMap<String, String> {
+        // Start with the environment that we might have inherited from the Zed CLI.
+        let mut env = self
+            .environment
+            .read(cx)
+            .get_cli_environment()
+            .unwrap_or_default();
+        // Then extend it with the env files and the explicit env variables from the settings,
+        // so they take precedence.
+        env.extend(lookups.env_file_vars);
+        env.extend(settings.env.clone());
+        #[cfg(windows)]
+        {
+            env = uppercase_env_keys(env);
+        }
 
-    fn activate_python_virtual_environment(
-        &self,
-        command: String,
-        terminal_handle: &Model<Terminal>,
-        cx: &mut ModelContext<Project>,
-    ) {
-        terminal_handle.update(cx, |this, _| this.input_bytes(command.into_bytes()));
-    }
+        if let TerminalKind::Task(spawn_task) = kind {
+            // Let the toolchain pinned by the project apply to the task without a `rustup override`,
+            // unless the task sets its own.
+            if let Some(channel) = lookups.rust_toolchain {
+                env.insert("RUSTUP_TOOLCHAIN".to_string(), channel);
+            }
+            #[cfg(not(windows))]
+            env.extend(spawn_task.env.clone());
+            // Otherwise, a task's `Path` would end up next to the inherited `PATH`, instead of replacing it.
+            #[cfg(windows)]
+            env.extend(uppercase_env_keys(spawn_task.env.clone()));
 
-    pub fn local_terminal_handles(&self) -> &Vec<WeakModel<terminal::Terminal>> {
+            if let Some(venv_path) = python_venv_directory {
+                env.insert(
+                    "VIRTUAL_ENV".to_string(),
+                    venv_path.to_string_lossy().to_string(),
+                );
+            }
+        }
+
+        if is_ssh_terminal {
+            // Both shells and tasks get here with the settings' and the task's variables already in `env`,
+            // so the defaults never override what the user has set, e.g. `TERM` in `terminal.env`.
+            add_ssh_env_defaults(&mut env, &settings.remote_locale);
+        } else if let TerminalKind::Task(_) = kind {
+            // CI containers and restricted shells may not set it, breaking the tools that rely on `~`.
+            if !cfg!(windows) && !env.contains_key("HOME") {
+                match env::var_os("HOME")
+                    .filter(|home| !home.is_empty())
+                    .map(PathBuf::from)
+                    .or_else(dirs::home_dir)
+                {
+                    Some(home) => {
+                        env.insert("HOME".to_string(), home.to_string_lossy().to_string());
+                    }
+                    None => log::warn!(
+                        "Could not determine the home directory to set HOME for the task"
+                    ),
+                }
+            }
+            if let Some(toolchain_paths) = lookups.asdf_toolchain_paths {
+                for (_, bin_path) in toolchain_paths.into_iter().sorted() {
+                    add_environment_path(&mut env, &bin_path, &lookups.path_files).log_err();
+                }
+            }
+            // Added last, so that the venv takes precedence over an asdf-managed Python.
+            if let Some(venv_path) = python_venv_directory {
+                if lookups.is_conda_env {
+                    add_conda_env(&mut env, venv_path, &lookups.path_files).log_err();
+                } else {
+                    add_environment_path(&mut env, &venv_path.join("bin"), &lookups.path_files)
+                        .log_err();
+                }
+                inject_pth_paths(&mut env, lookups.python_pth_paths).log_err();
+            }
+        } else if let Some(venv_path) = python_venv_directory
+            .filter(|_| matches!(kind, TerminalKind::Shell(_)))
+            .filter(|_| lookups.is_conda_env && !lookups.conda_available)
+        {
+            // Shells activate conda environments with `conda activate`, when it is available.
+            add_conda_env(&mut env, venv_path, &lookups.path_files).log_err();
+        }
+
+        env
+    }
+
+    fn record_task_output(&mut self, task_id: TaskId, output: String, cx: &AppContext) {
+        let history_len = TerminalSettings::get_global(cx).task_output_history;
+        if history_len == 0 {
+            self.terminals.task_outputs.remove(&task_id);
+            return;
+        }
+        let outputs = self.terminals.task_outputs.entry(task_id).or_default();
+        outputs.push_back(output);
+        while outputs.len() > history_len {
+            outputs.pop_front();
+        }
+    }
+
+    /// Whether the given task has finished at least twice, so its latest outputs can be compared.
+    pub fn can_diff_task_output(&self, task_id: &TaskId) -> bool {
+        self.terminals
+            .task_outputs
+            .get(task_id)
+            .is_some_and(|outputs| outputs.len() >= 2)
+    }
+
+    /// A unified, line-level diff between the two latest outputs of the given task,
+    /// if the task has finished at least twice.
+    pub fn task_output_diff(&self, task_id: &TaskId) -> Option<String> {
+        let outputs = self.terminals.task_outputs.get(task_id)?;
+        let mut latest_outputs = outputs.iter().rev();
+        let current = latest_outputs.next()?;
+        let previous = latest_outputs.next()?;
+        let diff = similar::TextDiff::configure()
+            .algorithm(similar::Algorithm::Myers)
+            .diff_lines(previous.as_str(), current.as_str());
+        Some(
+            diff.unified_diff()
+                .header("previous run", "current run")
+                .to_string(),
+        )
+    }
+
+    fn python_venv_directory(
+        &self,
+        path: Option<&Path>,
+        settings: &TerminalSettings,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Option<PathBuf>> {
+        let Some(path) = path else {
+            return Task::ready(None);
+        };
+        let Some(venv_settings) = settings.detect_venv.as_option() else {
+            return Task::ready(None);
+        };
+        let path: Arc<Path> = Arc::from(path);
+        // A venv activated before starting Zed (e.g. in CI or in a container) is used as is.
+        let Some(activated_venv) = env::var_os("VIRTUAL_ENV")
+            .map(PathBuf::from)
+            .filter(|_| self.is_local())
+        else {
+            return self.detect_python_venv_directory(path, venv_settings, cx);
+        };
+        let fs = self.fs.clone();
+        cx.spawn(|project, mut cx| async move {
+            if is_python_venv(fs.as_ref(), &activated_venv).await {
+                return Some(activated_venv);
+            }
+            project
+                .update(&mut cx, |project, cx| {
+                    project.detect_python_venv_directory(path, venv_settings, cx)
+                })
+                .ok()?
+                .await
+        })
+    }
+
+    /// The venv found for the path in the configured directories, cached for the configured time.
+    fn detect_python_venv_directory(
+        &self,
+        path: Arc<Path>,
+        venv_settings: terminal_settings::VenvSettingsContent,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Option<PathBuf>> {
+        if let Some((venv_path, found_at)) = self.terminals.venv_cache.get(&path) {
+            if found_at.elapsed() < venv_settings.cache_ttl {
+                return Task::ready(Some(venv_path.clone()));
+            }
+        }
+
+        let venv_path = self.find_venv_directory(path.clone(), venv_settings, cx);
+        cx.spawn(|project, mut cx| async move {
+            let venv_path = venv_path.await;
+            project
+                .update(&mut cx, |project, _| match &venv_path {
+                    Some(venv_path) => {
+                        project
+                            .terminals
+                            .venv_cache
+                            .insert(path, (venv_path.clone(), Instant::now()));
+                    }
+                    None => {
+                        project.terminals.venv_cache.remove(&path);
+                    }
+                })
+                .ok();
+            venv_path
+        })
+    }
+
+    /// Forgets the cached virtual environments that contain any of the changed worktree entries.
+    pub(crate) fn invalidate_venv_cache(
+        &mut self,
+        worktree_abs_path: &Path,
+        changes: &UpdatedEntriesSet,
+    ) {
+        if self.terminals.venv_cache.is_empty() {
+            return;
+        }
+        for (path, _, _) in changes.iter() {
+            let abs_path = worktree_abs_path.join(path);
+            self.terminals
+                .venv_cache
+                .retain(|_, (venv_path, _)| !abs_path.starts_with(venv_path));
+        }
+    }
+
+    /// Looks for a Python virtual environment in the configured directories under `abs_path`:
+    /// first among the worktree entries, then on the file system, to also find the venvs
+    /// that are not part of the worktree (e.g. ignored or symlinked from a cache directory).
+    /// If several venvs are found, the one created by `uv` is preferred.
+    pub fn find_venv_directory(
+        &self,
+        abs_path: Arc<Path>,
+        venv_settings: terminal_settings::VenvSettingsContent,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Option<PathBuf>> {
+        let bin_dir_name = match std::env::consts::OS {
+            "windows" => "Scripts",
+            _ => "bin",
+        };
+        let worktree_venvs = venv_settings
+            .directories
+            .iter()
+            .map(|name| abs_path.join(name))
+            .filter(|venv_path| {
+                let bin_path = venv_path.join(bin_dir_name);
+                self.find_worktree(&bin_path, cx)
+                    .and_then(|(worktree, relative_path)| {
+                        worktree.read(cx).entry_for_path(&relative_path)
+                    })
+                    .is_some_and(|entry| entry.is_dir())
+            })
+            .collect::<Vec<_>>();
+        if worktree_venvs.len() == 1 {
+            return Task::ready(worktree_venvs.into_iter().next());
+        }
+
+        let Some(fs) = self
+            .find_worktree(&abs_path, cx)
+            .and_then(|(worktree, _)| Some(worktree.read(cx).as_local()?.fs().clone()))
+        else {
+            return Task::ready(worktree_venvs.into_iter().next());
+        };
+        cx.background_executor().spawn(async move {
+            if !worktree_venvs.is_empty() {
+                return prefer_uv_venv(fs.as_ref(), worktree_venvs).await;
+            }
+            let mut venvs = Vec::new();
+            for venv_path in venv_settings
+                .directories
+                .iter()
+                .map(|name| abs_path.join(name))
+            {
+                // Resolve symlinked venvs (e.g. `.venv` pointing into a cache directory outside the worktree)
+                // before looking for their `bin` directory, which itself may be a symlink too.
+                let Ok(venv_path) = fs.canonicalize(&venv_path).await else {
+                    continue;
+                };
+                let is_venv = fs
+                    .metadata(&venv_path.join(bin_dir_name))
+                    .await
+                    .ok()
+                    .flatten()
+                    .map_or(false, |meta| meta.is_dir);
+                if is_venv && !venvs.contains(&venv_path) {
+                    venvs.push(venv_path);
+                }
+            }
+            prefer_uv_venv(fs.as_ref(), venvs).await
+        })
+    }
+
+    /// The command that activates the given Python virtual environment, when typed into a terminal:
+    /// e.g. `source '/project/.venv/bin/activate'\n`, for the shell configured by
+    /// `terminal.detect_venv.activate_script` in the venv's settings. The command is quoted for that shell
+    /// and ends with the line ending that submits it (`\r` for the shells on Windows, other than Nushell).
+    ///
+    /// Returns `None` if the venv detection is turned off, or the script path cannot be quoted.
+    pub fn python_activate_command(
+        &self,
+        venv_base_directory: &Path,
+        cx: &AppContext,
+    ) -> Option<String> {
+        let settings = self.terminal_settings(Some(venv_base_directory), cx);
+        let venv_settings = settings.detect_venv.as_option()?;
+        let activate_script_name = match venv_settings.activate_script {
+            terminal_settings::ActivateScript::Default => "activate",
+            terminal_settings::ActivateScript::Csh => "activate.csh",
+            terminal_settings::ActivateScript::Fish => "activate.fish",
+            terminal_settings::ActivateScript::Nushell => "activate.nu",
+            terminal_settings::ActivateScript::PowerShell => "activate.ps1",
+        };
+        let path = venv_base_directory
+            .join(match std::env::consts::OS {
+                "windows" => "Scripts",
+                _ => "bin",
+            })
+            .join(activate_script_name)
+            .to_string_lossy()
+            .to_string();
+        activate_script_command(venv_settings.activate_script, &path)
+    }
+
+    fn activate_python_virtual_environment(
+        &self,
+        command: String,
+        terminal_handle: &Model<Terminal>,
+        cx: &mut ModelContext<Project>,
+    ) {
+        terminal_handle.update(cx, |this, _| this.input_bytes(command.into_bytes()));
+    }
+
+    /// The number of tasks that are running in the local terminals.
+    pub fn running_task_count(&self, cx: &AppContext) -> usize {
+        self.terminals
+            .local_handles
+            .values()
+            .filter_map(|handle| handle.upgrade())
+            .filter(|terminal| {
+                terminal
+                    .read(cx)
+                    .task()
+                    .is_some_and(|task| task.status == TaskStatus::Running)
+            })
+            .count()
+    }
+
+    /// Notifies the observers when the tasks start or stop running:
+    /// when [`Project::running_task_count`] changes from zero, or to zero.
+    fn update_tasks_running(&mut self, cx: &mut ModelContext<Self>) {
+        let tasks_running = self.running_task_count(cx) > 0;
+        if tasks_running != self.terminals.tasks_running {
+            self.terminals.tasks_running = tasks_running;
+            cx.notify();
+        }
+    }
+
+    pub fn local_terminal_handles(&self) -> &HashMap<EntityId, WeakModel<terminal::Terminal>> {
         &self.terminals.local_handles
     }
+
+    /// Returns all local terminals that are still alive, skipping the released ones, in no particular order.
+    pub fn live_terminals(&self) -> impl Iterator<Item = Model<Terminal>> + '_ {
+        self.terminals
+            .local_handles
+            .values()
+            .filter_map(|handle| handle.upgrade())
+    }
 }
 
 pub fn wrap_for_ssh(
     ssh_command: &SshCommand,
     command: Option<(&String, &Vec<String>)>,
+    shell: Option<&Shell>,
     path: Option<&Path>,
     env: HashMap<String, String>,
+    forward_env_keys: &[&str],
     venv_directory: Option<PathBuf>,
-) -> (String, Vec<String>) {
+    remote_shell: RemoteShell,
+) -> anyhow::Result<(String, Vec<String>)> {
+    ssh_command.validate()?;
     let to_run = if let Some((command, args)) = command {
         let command = Cow::Borrowed(command.as_str());
-        let args = args.iter().filter_map(|arg| shlex::try_quote(arg).ok());
+        let args = args
+            .iter()
+            .filter_map(|arg| quote_for_remote_shell(remote_shell, arg));
         iter::once(command).chain(args).join(" ")
     } else {
-        "exec ${SHELL:-sh} -l".to_string()
+        remote_shell_command(remote_shell, shell)
     };
 
-    let mut env_changes = String::new();
+    // Lets the remote tools (e.g. `delta`) detect Zed, the same way as in the local terminals.
+    // Set first, so that the user's own `TERM_PROGRAM`, if any, overrides it.
+    let mut env_changes = env_change(remote_shell, "TERM_PROGRAM", "zed").unwrap_or_default();
     for (k, v) in env.iter() {
-        if let Some((k, v)) = shlex::try_quote(k).ok().zip(shlex::try_quote(v).ok()) {
-            env_changes.push_str(&format!("{}={} ", k, v));
+        // Passed by `ssh` itself with `SendEnv`, from the local environment.
+        if forward_env_keys.contains(&k.as_str()) {
+            continue;
+        }
+        let env_change = if v.contains('\n') {
+            multiline_env_change(remote_shell, k, v)
+        } else {
+            env_change(remote_shell, k, v)
+        };
+        if let Some(env_change) = env_change {
+            env_changes.push_str(&env_change);
         }
     }
     if let Some(venv_directory) = venv_directory {
-        if let Ok(str) = shlex::try_quote(venv_directory.to_string_lossy().as_ref()) {
-            env_changes.push_str(&format!("PATH={}:$PATH ", str));
+        if let Some(str) = remote_home_path(remote_shell, &venv_directory).or_else(|| {
+            quote_for_remote_shell(remote_shell, venv_directory.to_string_lossy().as_ref())
+        }) {
+            env_changes.push_str(&match remote_shell {
+                RemoteShell::Sh | RemoteShell::Bash => format!("PATH={}:$PATH ", str),
+                RemoteShell::Fish => format!("set -x PATH {} $PATH; ", str),
+                RemoteShell::Nushell => format!("$env.PATH = ($env.PATH | prepend {}); ", str),
+            });
         }
     }
 
     let commands = if let Some(path) = path {
-        let path_string = path.to_string_lossy().to_string();
-        // shlex will wrap the command in single quotes (''), disabling ~ expansion,
-        // replace ith with something that works
-        let tilde_prefix = "~/";
-        if path.starts_with(tilde_prefix) {
-            let trimmed_path = path_string
-                .trim_start_matches("/")
-                .trim_start_matches("~")
-                .trim_start_matches("/");
-
-            format!("cd \"$HOME/{trimmed_path}\"; {env_changes} {to_run}")
-        } else {
-            format!("cd {path:?}; {env_changes} {to_run}")
+        match remote_home_path(remote_shell, path) {
+            Some(path) => format!("cd {path}; {env_changes} {to_run}"),
+            None => format!("cd {path:?}; {env_changes} {to_run}"),
         }
     } else {
         format!("cd; {env_changes} {to_run}")
     };
-    let shell_invocation = format!("sh -c {}", shlex::try_quote(&commands).unwrap());
+    let interpreter = match remote_shell {
+        RemoteShell::Sh => "sh",
+        RemoteShell::Bash => "bash",
+        RemoteShell::Fish => "fish",
+        RemoteShell::Nushell => "nu",
+    };
+    let shell_invocation = format!("{interpreter} -c {}", single_quote(&commands));
 
     let program = "ssh".to_string();
-    let mut args = ssh_command.arguments.clone();
+    let mut args = Vec::new();
+    // Options must come before the destination, which is among the arguments.
+    for key in forward_env_keys {
+        args.push("-o".to_string());
+        args.push(format!("SendEnv={key}"));
+    }
+    if let Some(proxy_command) = SshCommand::proxy_command(&ssh_command.proxy_chain) {
+        args.push("-o".to_string());
+        args.push(format!("ProxyCommand={proxy_command}"));
+    }
+    args.extend(ssh_command.arguments.iter().cloned());
 
+    if let Some(flag) = ssh_command.x11_forwarding.ssh_flag() {
+        if env::var_os("DISPLAY").is_none() {
+            log::warn!("X11 forwarding is enabled for the ssh terminal, but DISPLAY is not set");
+        }
+        args.push(flag.to_string());
+    }
     args.push("-t".to_string());
     args.push(shell_invocation);
-    (program, args)
+    Ok((program, args))
 }
 
-fn add_environment_path(env: &mut HashMap<String, String>, new_path: &Path) -> anyhow::Result<()> {
+/// Builds the `wsl.exe` invocation that starts a login shell in the given distribution.
+/// `wsl.exe` translates the Windows working directory into the distribution's path itself.
+pub fn wrap_for_wsl(distro: &str, cwd: Option<&Path>) -> (String, Vec<String>) {
+    let cwd = cwd.map_or_else(|| "~".to_string(), |cwd| cwd.to_string_lossy().to_string());
+    let args = ["-d", distro, "--cd", cwd.as_str(), "--", "sh", "-l"]
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    ("wsl.exe".to_string(), args)
+}
+
+/// Translates a Windows path into the one it is mounted at in WSL, e.g. `C:\Users` into `/mnt/c/Users`.
+/// Paths that are not on a drive (e.g. UNC paths) have no such translation.
+fn wsl_path(path: &Path) -> Option<String> {
+    let path = path.to_string_lossy();
+    let mut chars = path.chars();
+    let drive = chars.next().filter(char::is_ascii_alphabetic)?;
+    if chars.next() != Some(':') {
+        return None;
+    }
+    let rest = chars.as_str().replace('\\', "/");
+    let rest = rest.trim_end_matches('/');
+    if !rest.is_empty() && !rest.starts_with('/') {
+        return None;
+    }
+    Some(format!("/mnt/{}{rest}", drive.to_ascii_lowercase()))
+}
+
+/// Sets the variable to the value quoted for the remote shell.
+fn env_change(remote_shell: RemoteShell, key: &str, value: &str) -> Option<String> {
+    let value = quote_for_remote_shell(remote_shell, value)?;
+    match remote_shell {
+        RemoteShell::Sh | RemoteShell::Bash => shlex::try_quote(key)
+            .ok()
+            .map(|key| format!("{key}={value} ")),
+        RemoteShell::Fish => {
+            quote_for_remote_shell(remote_shell, key).map(|key| format!("set -x {key} {value}; "))
+        }
+        RemoteShell::Nushell => {
+            quote_for_remote_shell(remote_shell, key).map(|key| format!("$env.{key} = {value}; "))
+        }
+    }
+}
+
+/// Sets the variable, which value has newlines, by decoding the value from base64 on the remote host,
+/// so that the newlines do not have to survive the quoting through ssh and the remote shell.
+/// The command substitution drops the trailing newlines of the value, the same way the shells do.
+fn multiline_env_change(remote_shell: RemoteShell, key: &str, value: &str) -> Option<String> {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(value);
+    match remote_shell {
+        RemoteShell::Sh | RemoteShell::Bash => shlex::try_quote(key)
+            .ok()
+            .map(|key| format!("{key}=\"$(echo '{encoded}' | base64 -d)\" ")),
+        RemoteShell::Fish => quote_for_remote_shell(remote_shell, key)
+            .map(|key| format!("set -x {key} (echo '{encoded}' | base64 -d | string collect); ")),
+        RemoteShell::Nushell => quote_for_remote_shell(remote_shell, key)
+            .map(|key| format!("$env.{key} = ('{encoded}' | decode base64 | decode); ")),
+    }
+}
+
+/// Whether the directory is a Python virtual environment, i.e. has its own interpreter.
+async fn is_python_venv(fs: &dyn Fs, venv_path: &Path) -> bool {
+    let interpreter = match std::env::consts::OS {
+        "windows" => Path::new("Scripts").join("python.exe"),
+        _ => Path::new("bin").join("python"),
+    };
+    fs.is_file(&venv_path.join(interpreter)).await
+}
+
+/// The first of the venvs created by `uv`, or the first venv, if none was.
+async fn prefer_uv_venv(fs: &dyn Fs, venvs: Vec<PathBuf>) -> Option<PathBuf> {
+    if venvs.len() > 1 {
+        for venv_path in &venvs {
+            let is_uv_venv = fs
+                .load(&venv_path.join("pyvenv.cfg"))
+                .await
+                .is_ok_and(|pyvenv_cfg| is_uv_pyvenv_cfg(&pyvenv_cfg));
+            if is_uv_venv {
+                return Some(venv_path.clone());
+            }
+        }
+    }
+    venvs.into_iter().next()
+}
+
+/// Whether the `pyvenv.cfg` of a venv says that `uv` created it, with e.g. a `uv = 0.4.20` line.
+fn is_uv_pyvenv_cfg(pyvenv_cfg: &str) -> bool {
+    pyvenv_cfg
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .any(|(key, _)| key.trim() == "uv")
+}
+
+/// Whether the directory is a Conda environment rather than a venv, i.e. has its package metadata.
+async fn is_conda_env(fs: &dyn Fs, env_path: &Path) -> bool {
+    fs.is_dir(&env_path.join("conda-meta")).await
+}
+
+/// Sets the variables `conda activate` would set for the environment, for when `conda` itself is not on the PATH:
+/// `CONDA_PREFIX`, `CONDA_DEFAULT_ENV` (named after the environment directory, shown in the prompt as `(name)`)
+/// and the environment's binaries prepended to `PATH`.
+fn add_conda_env(
+    env: &mut HashMap<String, String>,
+    env_path: &Path,
+    path_files: &HashSet<PathBuf>,
+) -> anyhow::Result<()> {
+    let name = env_path
+        .file_name()
+        .with_context(|| format!("conda environment {env_path:?} has no name"))?;
+    env.insert(
+        "CONDA_PREFIX".to_string(),
+        env_path.to_string_lossy().to_string(),
+    );
+    env.insert(
+        "CONDA_DEFAULT_ENV".to_string(),
+        name.to_string_lossy().to_string(),
+    );
+    match std::env::consts::OS {
+        "windows" => {
+            add_environment_path(env, &env_path.join("Scripts"), path_files)?;
+            add_environment_path(env, env_path, path_files)
+        }
+        _ => add_environment_path(env, &env_path.join("bin"), path_files),
+    }
+}
+
+/// The command line that runs the given venv activation script in the shell it is written for.
+fn activate_script_command(
+    activate_script: terminal_settings::ActivateScript,
+    script_path: &str,
+) -> Option<String> {
+    let activate_keyword = match activate_script {
+        terminal_settings::ActivateScript::Default => match std::env::consts::OS {
+            "windows" => ".",
+            _ => "source",
+        },
+        terminal_settings::ActivateScript::Nushell => "overlay use",
+        terminal_settings::ActivateScript::PowerShell => ".",
+        _ => "source",
+    };
+    let (quoted, line_ending) = match activate_script {
+        // Nushell takes paths with spaces as backtick strings, and its scripts use `\n` on every platform.
+        // Backtick strings have no escapes, so the paths with backticks are double quoted instead.
+        terminal_settings::ActivateScript::Nushell => {
+            let quoted = if script_path.contains('`') {
+                quote_for_remote_shell(RemoteShell::Nushell, script_path)?
+            } else {
+                format!("`{script_path}`")
+            };
+            (quoted, "\n")
+        }
+        _ => (
+            shlex::try_quote(script_path).ok()?.into_owned(),
+            match std::env::consts::OS {
+                "windows" => "\r",
+                _ => "\n",
+            },
+        ),
+    };
+    Some(format!("{} {}{}", activate_keyword, quoted, line_ending))
+}
+
+/// The command that starts the interactive shell on the remote host: the configured one,
+/// if it is installed there, or the user's login shell otherwise.
+fn remote_shell_command(remote_shell: RemoteShell, shell: Option<&Shell>) -> String {
+    let login_shell = match remote_shell {
+        RemoteShell::Sh | RemoteShell::Bash => "exec ${SHELL:-sh} -l".to_string(),
+        RemoteShell::Fish => "exec fish -l".to_string(),
+        RemoteShell::Nushell => "exec nu -l".to_string(),
+    };
+    let (program, args) = match shell {
+        None | Some(Shell::System) | Some(Shell::Login { program: None }) => return login_shell,
+        Some(Shell::Program(program)) => (program, Vec::new()),
+        Some(Shell::WithArguments { program, args, .. }) => (program, args.clone()),
+        Some(Shell::Login {
+            program: Some(program),
+        }) => (program, vec!["-l".to_string()]),
+    };
+    let Some(program) = quote_for_remote_shell(remote_shell, program) else {
+        return login_shell;
+    };
+    let command = iter::once(program.clone())
+        .chain(
+            args.iter()
+                .filter_map(|arg| quote_for_remote_shell(remote_shell, arg)),
+        )
+        .join(" ");
+    match remote_shell {
+        RemoteShell::Sh | RemoteShell::Bash => format!(
+            "if command -v {program} >/dev/null 2>&1; then exec {command}; else {login_shell}; fi"
+        ),
+        RemoteShell::Fish => {
+            format!("if command -q {program}; exec {command}; else; {login_shell}; end")
+        }
+        RemoteShell::Nushell => format!(
+            "if (which {program} | is-not-empty) {{ exec {command} }} else {{ {login_shell} }}"
+        ),
+    }
+}
+
+/// Spells a `~`-prefixed path, e.g. a task's working directory, relative to the remote `$HOME`.
+/// Quoting the path as is would disable the `~` expansion, so the remote shell would look
+/// for a literal `~` directory instead.
+fn remote_home_path(remote_shell: RemoteShell, path: &Path) -> Option<String> {
+    if !path.starts_with("~") {
+        return None;
+    }
+    let path_string = path.to_string_lossy();
+    let trimmed_path = path_string.trim_start_matches('~').trim_start_matches('/');
+    Some(match remote_shell {
+        RemoteShell::Nushell => format!("$\"($env.HOME)/{trimmed_path}\""),
+        RemoteShell::Sh | RemoteShell::Bash | RemoteShell::Fish => {
+            format!("\"$HOME/{trimmed_path}\"")
+        }
+    })
+}
+
+/// Quotes the string for the remote shell, so that it is passed as a single word.
+fn quote_for_remote_shell(remote_shell: RemoteShell, s: &str) -> Option<String> {
+    match remote_shell {
+        RemoteShell::Sh | RemoteShell::Bash => shlex::try_quote(s).ok().map(Cow::into_owned),
+        // Fish single quotes only support `\\` and `\'` escapes.
+        RemoteShell::Fish => Some(format!("'{}'", s.replace('\\', r"\\").replace('\'', r"\'"))),
+        // Nushell double quotes support C-like escapes.
+        RemoteShell::Nushell => Some(format!(
+            "\"{}\"",
+            s.replace('\\', r"\\").replace('"', r#"\""#)
+        )),
+    }
+}
+
+/// Wraps the string into single quotes, escaping only the single quotes inside it,
+/// so that the already quoted fragments of the string are passed to the shell as is.
+fn single_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r#"'\''"#))
+}
+
+/// Parses the variables out of a `.env` file in the dotenv format: `KEY=value` lines,
+/// optionally prefixed with `export` and with the values wrapped in quotes.
+fn parse_env_file(contents: &str) -> impl Iterator<Item = (String, String)> + '_ {
+    contents.lines().filter_map(|line| {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = line.split_once('=')?;
+        let key = key.trim();
+        if key.is_empty() {
+            return None;
+        }
+        let value = value.trim();
+        let value = if let Some(value) = value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+        {
+            value.replace("\\n", "\n").replace("\\\"", "\"")
+        } else if let Some(value) = value
+            .strip_prefix('\'')
+            .and_then(|value| value.strip_suffix('\''))
+        {
+            value.to_string()
+        } else {
+            // Unquoted values may have trailing comments.
+            value
+                .split_once(" #")
+                .map_or(value, |(value, _)| value)
+                .trim_end()
+                .to_string()
+        };
+        Some((key.to_string(), value))
+    })
+}
+
+/// The `bin` directories of the tool versions pinned by the asdf version manager for the given directory,
+/// keyed by the tool name, e.g. `nodejs` or `ruby`.
+/// `.tool-versions` files are looked up from the directory to the file system root,
+/// with the closest file's version of each tool winning. Versions that are not installed are skipped.
+async fn asdf_toolchain_paths(fs: &dyn Fs, path: &Path) -> Option<HashMap<String, PathBuf>> {
+    let installs_dir = env::var_os("ASDF_DATA_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| util::paths::home_dir().join(".asdf"))
+        .join("installs");
+    let mut toolchain_paths = HashMap::default();
+    for directory in path.ancestors() {
+        let Ok(contents) = fs.load(&directory.join(".tool-versions")).await else {
+            continue;
+        };
+        for (tool, version) in parse_tool_versions(&contents) {
+            if toolchain_paths.contains_key(tool) {
+                continue;
+            }
+            let install_dir = match version.strip_prefix("path:") {
+                Some(install_dir) => PathBuf::from(install_dir),
+                None => installs_dir.join(tool).join(version),
+            };
+            let bin_path = install_dir.join("bin");
+            let is_installed = fs
+                .metadata(&bin_path)
+                .await
+                .ok()
+                .flatten()
+                .is_some_and(|metadata| metadata.is_dir);
+            if is_installed {
+                toolchain_paths.insert(tool.to_string(), bin_path);
+            }
+        }
+    }
+    if toolchain_paths.is_empty() {
+        None
+    } else {
+        Some(toolchain_paths)
+    }
+}
+
+/// The tools of a `.tool-versions` file with their preferred version,
+/// skipping the ones that defer to the system installation.
+fn parse_tool_versions(contents: &str) -> impl Iterator<Item = (&str, &str)> {
+    contents.lines().filter_map(|line| {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut words = line.split_whitespace();
+        let tool = words.next()?;
+        let version = words.next()?;
+        (version != "system").then_some((tool, version))
+    })
+}
+
+/// The `toolchain.channel` field of a `rust-toolchain.toml` file.
+fn rust_toolchain_channel(contents: &str) -> Option<String> {
+    #[derive(Deserialize)]
+    struct RustToolchainFile {
+        toolchain: RustToolchain,
+    }
+    #[derive(Deserialize)]
+    struct RustToolchain {
+        channel: Option<String>,
+    }
+
+    let file = toml::from_str::<RustToolchainFile>(contents).ok()?;
+    file.toolchain
+        .channel
+        .filter(|channel| !channel.trim().is_empty())
+}
+
+/// Whether the path is a `.git` directory or is inside one: terminals should not open there,
+/// even if the project has such a worktree (e.g. after editing a `COMMIT_EDITMSG`).
+fn is_in_git_directory(path: &Path) -> bool {
+    path.components()
+        .any(|component| component.as_os_str() == *git::DOT_GIT)
+}
+
+/// Variables of the local `ssh` process, e.g. to reach the SSH agent or to forward X11.
+const SSH_CONNECTION_ENV: &[&str] = &[
+    "SSH_AUTH_SOCK",
+    "SSH_ASKPASS",
+    "SSH_AGENT_PID",
+    "KRB5CCNAME",
+    "DISPLAY",
+];
+
+/// Splits the variables the local `ssh` process needs to connect from the ones to export on the remote host.
+/// The former describe the local machine, so exporting them remotely would break e.g. the agent forwarding.
+/// The variables forwarded with `SendEnv` are kept in both, as `ssh` reads them from its own environment.
+fn split_ssh_connection_env(
+    env: HashMap<String, String>,
+    forward_env_keys: &[&str],
+) -> (HashMap<String, String>, HashMap<String, String>) {
+    let (mut ssh_env, remote_env): (HashMap<_, _>, HashMap<_, _>) = env
+        .into_iter()
+        .partition(|(key, _)| SSH_CONNECTION_ENV.contains(&key.as_str()));
+    ssh_env.extend(
+        remote_env
+            .iter()
+            .filter(|(key, _)| forward_env_keys.contains(&key.as_str()))
+            .map(|(key, value)| (key.clone(), value.clone())),
+    );
+    (ssh_env, remote_env)
+}
+
+/// Variables that the remote host may lack or have unsuitable defaults for, unless already set in `env`.
+fn add_ssh_env_defaults(env: &mut HashMap<String, String>, remote_locale: &str) {
+    // Alacritty sets its terminfo to `alacritty`, this requiring hosts to have it installed
+    // to properly display colors.
+    // We do not have the luxury of assuming the host has it installed,
+    // so we set it to a default that does not break the highlighting via ssh.
+    env.entry("TERM".to_string())
+        .or_insert_with(|| "xterm-256color".to_string());
+    // Hosts without a locale configured fall back to `POSIX`, which garbles non-ASCII output.
+    if !env.contains_key("LC_ALL") && !env.contains_key("LANG") && !remote_locale.is_empty() {
+        env.insert("LC_ALL".to_string(), remote_locale.to_string());
+    }
+}
+
+/// Environment variable names are case-insensitive on Windows: keeps a single entry per name, under the uppercased name.
+/// Of the entries differing only in case, the one already uppercased wins.
+#[cfg(windows)]
+fn uppercase_env_keys(env: HashMap<String, String>) -> HashMap<String, String> {
+    let mut uppercased = HashMap::default();
+    for (key, value) in env {
+        let uppercase_key = key.to_uppercase();
+        if key == uppercase_key || !uppercased.contains_key(&uppercase_key) {
+            uppercased.insert(uppercase_key, value);
+        }
+    }
+    uppercased
+}
+
+/// The `PATH` variable of the environment, whatever the case of its name.
+fn env_path(env: &HashMap<String, String>) -> Option<&String> {
+    env.iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("PATH"))
+        .map(|(_, value)| value)
+}
+
+/// The entries of the given `PATH` values that are files rather than directories, e.g. from a misconfigured shell profile.
+/// The entries that cannot be checked are not included, as they may still be valid directories.
+async fn path_files(fs: &dyn Fs, path_values: &[String]) -> HashSet<PathBuf> {
+    let mut path_files = HashSet::default();
+    for path in path_values.iter().flat_map(std::env::split_paths).unique() {
+        let is_file = fs
+            .metadata(&path)
+            .await
+            .ok()
+            .flatten()
+            .is_some_and(|metadata| !metadata.is_dir);
+        if is_file {
+            path_files.insert(path);
+        }
+    }
+    path_files
+}
+
+fn add_environment_path(
+    env: &mut HashMap<String, String>,
+    new_path: &Path,
+    path_files: &HashSet<PathBuf>,
+) -> anyhow::Result<()> {
     let mut env_paths = vec![new_path.to_path_buf()];
     if let Some(path) = env.get("PATH").or(env::var("PATH").ok().as_ref()) {
-        let mut paths = std::env::split_paths(&path).collect::<Vec<_>>();
+        // Drop the entries known to be files, see [`path_files`].
+        let mut paths = std::env::split_paths(&path)
+            .filter(|path| !path_files.contains(path))
+            .collect::<Vec<_>>();
         env_paths.append(&mut paths);
     }
+    // Keep the first occurrence, so the prepended path still takes priority.
+    let mut seen = HashSet::<&Path>::default();
+    let env_paths = env_paths.iter().filter(|path| seen.insert(path.as_path()));
 
     let paths = std::env::join_paths(env_paths).context("failed to create PATH env variable")?;
     env.insert("PATH".to_string(), paths.to_string_lossy().to_string());
@@ -454,9 +1865,530 @@ fn add_environment_path(env: &mut HashMap<String, String>, new_path: &Path) -> a
     Ok(())
 }
 
+/// The paths listed in the venv's `*.pth` files, e.g. the source directories of the editable installs,
+/// created by `pip install -e .`. Only the existing directories are kept.
+async fn venv_pth_paths(fs: &dyn Fs, venv_directory: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut python_paths = Vec::new();
+    for site_packages in python_site_packages_directories(fs, venv_directory).await {
+        let Ok(mut entries) = fs.read_dir(&site_packages).await else {
+            continue;
+        };
+        let mut pth_paths = Vec::new();
+        while let Some(entry) = entries.next().await {
+            if let Some(path) = entry
+                .ok()
+                .filter(|path| path.extension().is_some_and(|extension| extension == "pth"))
+            {
+                pth_paths.push(path);
+            }
+        }
+        // Python's `site` module processes the `.pth` files in alphabetical order.
+        pth_paths.sort();
+        for pth_path in pth_paths {
+            let contents = fs
+                .load(&pth_path)
+                .await
+                .with_context(|| format!("reading {pth_path:?}"))?;
+            for path in pth_file_paths(&contents, &site_packages) {
+                if fs.is_dir(&path).await {
+                    python_paths.push(path);
+                }
+            }
+        }
+    }
+    Ok(python_paths)
+}
+
+/// Prepends the paths from the venv's `*.pth` files to `PYTHONPATH`, so that tasks that do not go
+/// through the venv's `python` binary are still able to import them.
+fn inject_pth_paths(
+    env: &mut HashMap<String, String>,
+    mut python_paths: Vec<PathBuf>,
+) -> anyhow::Result<()> {
+    if python_paths.is_empty() {
+        return Ok(());
+    }
+
+    if let Some(python_path) = env
+        .get("PYTHONPATH")
+        .or(env::var("PYTHONPATH").ok().as_ref())
+    {
+        python_paths.extend(std::env::split_paths(&python_path));
+    }
+    let python_paths = std::env::join_paths(python_paths.into_iter().unique())
+        .context("failed to create PYTHONPATH env variable")?;
+    env.insert(
+        "PYTHONPATH".to_string(),
+        python_paths.to_string_lossy().to_string(),
+    );
+
+    Ok(())
+}
+
+/// Parses the paths out of a `.pth` file's contents, the same way Python's `site` module does:
+/// blank lines, comments and `import` lines are skipped, relative paths are resolved against
+/// the `site-packages` directory.
+fn pth_file_paths<'a>(
+    contents: &'a str,
+    site_packages: &'a Path,
+) -> impl Iterator<Item = PathBuf> + 'a {
+    contents
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| {
+            !line.is_empty()
+                && !line.starts_with('#')
+                && !line.starts_with("import ")
+                && !line.starts_with("import\t")
+        })
+        .map(|line| site_packages.join(line))
+}
+
+/// `site-packages` directories of the venv: `Lib/site-packages` on Windows, `lib/pythonX.Y/site-packages` elsewhere.
+async fn python_site_packages_directories(fs: &dyn Fs, venv_directory: &Path) -> Vec<PathBuf> {
+    if cfg!(windows) {
+        return vec![venv_directory.join("Lib").join("site-packages")];
+    }
+    let Ok(mut entries) = fs.read_dir(&venv_directory.join("lib")).await else {
+        return Vec::new();
+    };
+    let mut directories = Vec::new();
+    while let Some(entry) = entries.next().await {
+        let Some(entry) = entry.ok().filter(|entry| {
+            entry
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with("python"))
+        }) else {
+            continue;
+        };
+        let site_packages = entry.join("site-packages");
+        if fs.is_dir(&site_packages).await {
+            directories.push(site_packages);
+        }
+    }
+    directories
+}
+
 #[cfg(test)]
 mod tests {
-    use collections::HashMap;
+    use collections::{HashMap, HashSet};
+
+    use remote::{RemoteShell, X11Forwarding};
+    use task::Shell;
+
+    use super::SshCommand;
+
+    fn ssh_env_value(value: &str) -> String {
+        let mut env = HashMap::default();
+        env.insert("FOO".to_string(), value.to_string());
+        let ssh_command = SshCommand::new(vec!["user@host".to_string()], X11Forwarding::Disabled);
+        let (program, args) = super::wrap_for_ssh(
+            &ssh_command,
+            Some((&"echo".to_string(), &vec!["$FOO".to_string()])),
+            None,
+            None,
+            env,
+            &[],
+            None,
+            RemoteShell::Sh,
+        )
+        .unwrap();
+        assert_eq!(program, "ssh");
+        assert_eq!(args[..2], ["user@host", "-t"]);
+
+        let invocation = shlex::split(&args[2]).unwrap();
+        assert_eq!(invocation[..2], ["sh", "-c"]);
+        let commands = shlex::split(&invocation[2]).unwrap();
+        assert_eq!(commands.last().unwrap(), "$FOO");
+        commands
+            .iter()
+            .find_map(|word| word.strip_prefix("FOO="))
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_wrap_for_ssh_env_quoting() {
+        assert_eq!(ssh_env_value("plain"), "plain");
+        assert_eq!(ssh_env_value("with spaces"), "with spaces");
+        assert_eq!(ssh_env_value(r#"say "hi""#), r#"say "hi""#);
+        assert_eq!(ssh_env_value("it's"), "it's");
+        assert_eq!(ssh_env_value(r#"'all' "of" them"#), r#"'all' "of" them"#);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_wrap_for_ssh_multiline_env_value() {
+        let value = "first line\n  'second' \"line\"\nthird";
+        let mut env = HashMap::default();
+        env.insert("FOO".to_string(), value.to_string());
+        let ssh_command = SshCommand::new(vec!["user@host".to_string()], X11Forwarding::Disabled);
+        let (_, args) = super::wrap_for_ssh(
+            &ssh_command,
+            Some((&"printenv".to_string(), &vec!["FOO".to_string()])),
+            None,
+            None,
+            env,
+            &[],
+            None,
+            RemoteShell::Sh,
+        )
+        .unwrap();
+
+        // Run what the remote host would, locally.
+        let invocation = shlex::split(args.last().unwrap()).unwrap();
+        assert_eq!(invocation[..2], ["sh", "-c"]);
+        let output = std::process::Command::new("sh")
+            .args(["-c", &invocation[2]])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            format!("{value}\n")
+        );
+    }
+
+    #[test]
+    fn test_wrap_for_ssh_non_posix_shells() {
+        let ssh_command = SshCommand::new(vec!["user@host".to_string()], X11Forwarding::Disabled);
+        let wrap = |env_value: &str, remote_shell| {
+            let mut env = HashMap::default();
+            env.insert("FOO".to_string(), env_value.to_string());
+            let (_, args) = super::wrap_for_ssh(
+                &ssh_command,
+                Some((&"echo".to_string(), &vec!["bar".to_string()])),
+                None,
+                Some(std::path::Path::new("~/project")),
+                env,
+                &[],
+                None,
+                remote_shell,
+            )
+            .unwrap();
+            args.last().unwrap().clone()
+        };
+
+        assert_eq!(
+            wrap("it's", RemoteShell::Fish),
+            format!(
+                "fish -c {}",
+                super::single_quote(
+                    r#"cd "$HOME/project"; set -x 'TERM_PROGRAM' 'zed'; set -x 'FOO' 'it\'s';  echo 'bar'"#
+                )
+            )
+        );
+        assert_eq!(
+            wrap(r#"say "hi""#, RemoteShell::Nushell),
+            format!(
+                "nu -c {}",
+                super::single_quote(
+                    r#"cd $"($env.HOME)/project"; $env."TERM_PROGRAM" = "zed"; $env."FOO" = "say \"hi\"";  echo "bar""#
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn test_wrap_for_ssh_configured_shell() {
+        let ssh_command = SshCommand::new(vec!["user@host".to_string()], X11Forwarding::Disabled);
+        let wrap = |shell: Option<&Shell>| {
+            let (_, args) = super::wrap_for_ssh(
+                &ssh_command,
+                None,
+                shell,
+                None,
+                HashMap::default(),
+                &[],
+                None,
+                RemoteShell::Bash,
+            )
+            .unwrap();
+            args.last().unwrap().clone()
+        };
+
+        let login_shell = format!(
+            "bash -c {}",
+            super::single_quote("cd; TERM_PROGRAM=zed  exec ${SHELL:-sh} -l")
+        );
+        assert_eq!(wrap(None), login_shell);
+        assert_eq!(wrap(Some(&Shell::System)), login_shell);
+        assert_eq!(
+            wrap(Some(&Shell::Program("zsh".to_string()))),
+            format!(
+                "bash -c {}",
+                super::single_quote(
+                    "cd; TERM_PROGRAM=zed  if command -v zsh >/dev/null 2>&1; then exec zsh; else exec ${SHELL:-sh} -l; fi"
+                )
+            )
+        );
+        assert_eq!(
+            wrap(Some(&Shell::WithArguments {
+                program: "tmux".to_string(),
+                args: vec!["new".to_string(), "-A".to_string()],
+                title_override: None,
+            })),
+            format!(
+                "bash -c {}",
+                super::single_quote(
+                    "cd; TERM_PROGRAM=zed  if command -v tmux >/dev/null 2>&1; then exec tmux new -A; else exec ${SHELL:-sh} -l; fi"
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn test_wrap_for_wsl() {
+        let (program, args) =
+            super::wrap_for_wsl("Ubuntu", Some(std::path::Path::new(r"C:\Users\me\project")));
+        assert_eq!(program, "wsl.exe");
+        assert_eq!(
+            args,
+            [
+                "-d",
+                "Ubuntu",
+                "--cd",
+                r"C:\Users\me\project",
+                "--",
+                "sh",
+                "-l"
+            ]
+        );
+
+        let (_, args) = super::wrap_for_wsl("Debian", None);
+        assert_eq!(args, ["-d", "Debian", "--cd", "~", "--", "sh", "-l"]);
+    }
+
+    #[test]
+    fn test_wsl_path() {
+        let wsl_path = |path: &str| super::wsl_path(std::path::Path::new(path));
+        assert_eq!(
+            wsl_path(r"C:\Users\me\.venv"),
+            Some("/mnt/c/Users/me/.venv".to_string())
+        );
+        assert_eq!(wsl_path(r"D:\"), Some("/mnt/d".to_string()));
+        assert_eq!(wsl_path("e:/work"), Some("/mnt/e/work".to_string()));
+        assert_eq!(wsl_path(r"\\server\share\project"), None);
+        assert_eq!(wsl_path("C:relative"), None);
+    }
+
+    #[test]
+    fn test_wrap_for_ssh_home_relative_paths() {
+        let ssh_command = SshCommand::new(vec!["user@host".to_string()], X11Forwarding::Disabled);
+        let (_, args) = super::wrap_for_ssh(
+            &ssh_command,
+            Some((&"make".to_string(), &Vec::new())),
+            None,
+            Some(std::path::Path::new("~/projects/foo")),
+            HashMap::default(),
+            &[],
+            Some(std::path::PathBuf::from("~/.venvs/foo/bin")),
+            RemoteShell::Sh,
+        )
+        .unwrap();
+        assert_eq!(
+            args.last().unwrap(),
+            &format!(
+                "sh -c {}",
+                super::single_quote(
+                    r#"cd "$HOME/projects/foo"; TERM_PROGRAM=zed PATH="$HOME/.venvs/foo/bin":$PATH  make"#
+                )
+            )
+        );
+
+        assert_eq!(
+            super::remote_home_path(RemoteShell::Bash, std::path::Path::new("~")),
+            Some(r#""$HOME/""#.to_string())
+        );
+        assert_eq!(
+            super::remote_home_path(RemoteShell::Bash, std::path::Path::new("/srv/~foo")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_wrap_for_ssh_x11_forwarding() {
+        let args_for = |x11_forwarding| {
+            let ssh_command = SshCommand::new(vec!["user@host".to_string()], x11_forwarding);
+            let (_, args) = super::wrap_for_ssh(
+                &ssh_command,
+                None,
+                None,
+                None,
+                HashMap::default(),
+                &[],
+                None,
+                RemoteShell::Sh,
+            )
+            .unwrap();
+            args[..args.len() - 1].to_vec()
+        };
+
+        assert_eq!(args_for(X11Forwarding::Disabled), ["user@host", "-t"]);
+        assert_eq!(args_for(X11Forwarding::Trusted), ["user@host", "-Y", "-t"]);
+        assert_eq!(
+            args_for(X11Forwarding::Untrusted),
+            ["user@host", "-X", "-t"]
+        );
+    }
+
+    #[test]
+    fn test_wrap_for_ssh_proxy_chain() {
+        let hop = |host: &str| SshCommand::new(vec![host.to_string()], X11Forwarding::Disabled);
+        let ssh_command = SshCommand::new(vec!["user@target".to_string()], X11Forwarding::Disabled)
+            .with_proxy_chain(vec![hop("first@jump1"), hop("second@jump2")]);
+        let (_, args) = super::wrap_for_ssh(
+            &ssh_command,
+            None,
+            None,
+            None,
+            HashMap::default(),
+            &[],
+            None,
+            RemoteShell::Sh,
+        )
+        .unwrap();
+
+        assert_eq!(
+            args[..4],
+            [
+                "-o",
+                r#"ProxyCommand=ssh -o "ProxyCommand=ssh first@jump1 -W '%%h:%%p'" second@jump2 -W '%h:%p'"#,
+                "user@target",
+                "-t",
+            ]
+        );
+        assert_eq!(args.len(), 5);
+    }
+
+    #[test]
+    fn test_ssh_command_validation() {
+        let validate = |arguments: &[&str]| {
+            SshCommand::new(
+                arguments.iter().map(|arg| arg.to_string()).collect(),
+                X11Forwarding::Disabled,
+            )
+            .validate()
+        };
+
+        assert!(validate(&["user@host"]).is_ok());
+        assert!(validate(&["-p", "2222", "-o", "RequestTTY=yes", "user@host"]).is_ok());
+        assert!(validate(&["-T", "user@host"]).is_err());
+        assert!(validate(&["-N", "user@host"]).is_err());
+        assert!(validate(&["-o", "RequestTTY=no", "user@host"]).is_err());
+        assert!(validate(&["-oRequestTTY no", "user@host"]).is_err());
+
+        let result = super::wrap_for_ssh(
+            &SshCommand::new(
+                vec!["-T".to_string(), "user@host".to_string()],
+                X11Forwarding::Disabled,
+            ),
+            None,
+            None,
+            None,
+            HashMap::default(),
+            &[],
+            None,
+            RemoteShell::Sh,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_is_uv_pyvenv_cfg() {
+        assert!(super::is_uv_pyvenv_cfg(
+            "home = /usr/bin\nimplementation = CPython\nuv = 0.4.20\nversion_info = 3.12.3\n"
+        ));
+        assert!(!super::is_uv_pyvenv_cfg(
+            "home = /usr/bin\ninclude-system-site-packages = false\nversion = 3.12.3\n"
+        ));
+    }
+
+    #[test]
+    fn test_split_ssh_connection_env() {
+        let mut env = HashMap::default();
+        env.insert("SSH_AUTH_SOCK".to_string(), "/tmp/agent.sock".to_string());
+        env.insert("DISPLAY".to_string(), ":0".to_string());
+        env.insert("FOO".to_string(), "bar".to_string());
+
+        let (ssh_env, remote_env) = super::split_ssh_connection_env(env.clone(), &[]);
+        assert_eq!(ssh_env.len(), 2);
+        assert_eq!(ssh_env.get("SSH_AUTH_SOCK").unwrap(), "/tmp/agent.sock");
+        assert_eq!(ssh_env.get("DISPLAY").unwrap(), ":0");
+        assert_eq!(remote_env.len(), 1);
+        assert_eq!(remote_env.get("FOO").unwrap(), "bar");
+
+        let (ssh_env, remote_env) = super::split_ssh_connection_env(env, &["FOO"]);
+        assert_eq!(ssh_env.len(), 3);
+        assert_eq!(ssh_env.get("FOO").unwrap(), "bar");
+        assert_eq!(remote_env.get("FOO").unwrap(), "bar");
+    }
+
+    #[test]
+    fn test_wrap_for_ssh_send_env() {
+        let mut env = HashMap::default();
+        env.insert("TOKEN".to_string(), "it's a secret".to_string());
+        env.insert("FOO".to_string(), "bar".to_string());
+        let (_, args) = super::wrap_for_ssh(
+            &SshCommand::new(vec!["user@host".to_string()], X11Forwarding::Disabled),
+            None,
+            None,
+            None,
+            env,
+            &["TOKEN"],
+            None,
+            RemoteShell::Sh,
+        )
+        .unwrap();
+        assert_eq!(args[..3], ["-o", "SendEnv=TOKEN", "user@host"]);
+        let remote_command = args.last().unwrap();
+        assert!(remote_command.contains("FOO="));
+        assert!(!remote_command.contains("TOKEN"));
+        assert!(!remote_command.contains("secret"));
+    }
+
+    #[test]
+    fn test_ssh_env_defaults_keep_user_values() {
+        let mut env = HashMap::default();
+        super::add_ssh_env_defaults(&mut env, "C.UTF-8");
+        assert_eq!(env.get("TERM").unwrap(), "xterm-256color");
+        assert_eq!(env.get("LC_ALL").unwrap(), "C.UTF-8");
+
+        let mut env = HashMap::default();
+        env.insert("TERM".to_string(), "alacritty".to_string());
+        env.insert("LANG".to_string(), "de_DE.UTF-8".to_string());
+        super::add_ssh_env_defaults(&mut env, "C.UTF-8");
+        assert_eq!(env.get("TERM").unwrap(), "alacritty");
+        assert_eq!(env.get("LC_ALL"), None);
+
+        let (_, args) = super::wrap_for_ssh(
+            &SshCommand::new(vec!["user@host".to_string()], X11Forwarding::Disabled),
+            None,
+            None,
+            None,
+            env,
+            &[],
+            None,
+            RemoteShell::Sh,
+        )
+        .unwrap();
+        assert!(args.last().unwrap().contains("TERM=alacritty"));
+        assert!(!args.last().unwrap().contains("xterm-256color"));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_uppercase_env_keys() {
+        let mut env = HashMap::default();
+        env.insert("Path".to_string(), "/a".to_string());
+        env.insert("PATH".to_string(), "/b".to_string());
+        env.insert("foo".to_string(), "bar".to_string());
+
+        let env = super::uppercase_env_keys(env);
+        assert_eq!(env.len(), 2);
+        assert_eq!(env.get("PATH").unwrap(), "/b");
+        assert_eq!(env.get("FOO").unwrap(), "bar");
+    }
 
     #[test]
     fn test_add_environment_path_with_existing_path() {
@@ -470,7 +2402,7 @@ mod tests {
         env.insert("PATH".to_string(), old_path.to_string());
         env.insert("OTHER".to_string(), "aaa".to_string());
 
-        super::add_environment_path(&mut env, &tmp_path).unwrap();
+        super::add_environment_path(&mut env, &tmp_path, &HashSet::default()).unwrap();
         if cfg!(windows) {
             assert_eq!(env.get("PATH").unwrap(), &format!("/tmp/new;{}", old_path));
         } else {
@@ -485,7 +2417,7 @@ mod tests {
         let mut env = HashMap::default();
         env.insert("OTHER".to_string(), "aaa".to_string());
         let os_path = std::env::var("PATH").unwrap();
-        super::add_environment_path(&mut env, &tmp_path).unwrap();
+        super::add_environment_path(&mut env, &tmp_path, &HashSet::default()).unwrap();
         if cfg!(windows) {
             assert_eq!(env.get("PATH").unwrap(), &format!("/tmp/new;{}", os_path));
         } else {
@@ -493,4 +2425,316 @@ mod tests {
         }
         assert_eq!(env.get("OTHER").unwrap(), "aaa");
     }
+
+    #[test]
+    fn test_add_environment_path_deduplicates() {
+        let mut env = HashMap::default();
+        let old_path =
+            std::env::join_paths(["/usr/local/bin", "/usr/bin", "/usr/local/bin"]).unwrap();
+        env.insert("PATH".to_string(), old_path.to_string_lossy().to_string());
+
+        super::add_environment_path(
+            &mut env,
+            std::path::Path::new("/usr/bin"),
+            &HashSet::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            std::env::split_paths(env.get("PATH").unwrap()).collect::<Vec<_>>(),
+            [
+                std::path::PathBuf::from("/usr/bin"),
+                std::path::PathBuf::from("/usr/local/bin"),
+            ]
+        );
+    }
+
+    #[gpui::test]
+    async fn test_add_environment_path_skips_files(cx: &mut gpui::TestAppContext) {
+        let fs = fs::FakeFs::new(cx.executor());
+        let dir = std::path::Path::new("/dir");
+        let file = dir.join("not-a-directory");
+        fs.create_dir(dir).await.unwrap();
+        fs.insert_file(&file, Vec::new()).await;
+        let missing = dir.join("missing");
+
+        let mut env = HashMap::default();
+        let old_path = std::env::join_paths([dir, file.as_path(), missing.as_path()]).unwrap();
+        env.insert("PATH".to_string(), old_path.to_string_lossy().to_string());
+        let path_files =
+            super::path_files(fs.as_ref(), &[old_path.to_string_lossy().to_string()]).await;
+        assert_eq!(path_files.iter().collect::<Vec<_>>(), [&file]);
+
+        let new_path = std::path::PathBuf::from("/tmp/new");
+        super::add_environment_path(&mut env, &new_path, &path_files).unwrap();
+        assert_eq!(
+            std::env::split_paths(env.get("PATH").unwrap()).collect::<Vec<_>>(),
+            [new_path, dir.to_path_buf(), missing]
+        );
+    }
+
+    #[gpui::test]
+    async fn test_add_conda_env(cx: &mut gpui::TestAppContext) {
+        let fs = fs::FakeFs::new(cx.executor());
+        let conda_env = std::path::Path::new("/root/envs/myenv");
+        fs.create_dir(&conda_env.join("bin")).await.unwrap();
+        assert!(!super::is_conda_env(fs.as_ref(), conda_env).await);
+        fs.create_dir(&conda_env.join("conda-meta")).await.unwrap();
+        assert!(super::is_conda_env(fs.as_ref(), conda_env).await);
+
+        let mut env = HashMap::default();
+        env.insert("PATH".to_string(), "/usr/bin".to_string());
+        super::add_conda_env(&mut env, conda_env, &HashSet::default()).unwrap();
+        assert_eq!(env.get("CONDA_DEFAULT_ENV").unwrap(), "myenv");
+        assert_eq!(
+            env.get("CONDA_PREFIX").unwrap(),
+            &conda_env.to_string_lossy().to_string()
+        );
+        if !cfg!(windows) {
+            assert_eq!(
+                std::env::split_paths(env.get("PATH").unwrap()).collect::<Vec<_>>(),
+                [conda_env.join("bin"), std::path::PathBuf::from("/usr/bin")]
+            );
+        }
+    }
+
+    #[gpui::test]
+    async fn test_is_python_venv(cx: &mut gpui::TestAppContext) {
+        let fs = fs::FakeFs::new(cx.executor());
+        let venv = std::path::Path::new("/venv");
+        fs.create_dir(venv).await.unwrap();
+        assert!(!super::is_python_venv(fs.as_ref(), venv).await);
+
+        let bin_dir = venv.join(if cfg!(windows) { "Scripts" } else { "bin" });
+        fs.create_dir(&bin_dir).await.unwrap();
+        assert!(!super::is_python_venv(fs.as_ref(), venv).await);
+
+        fs.insert_file(
+            bin_dir.join(if cfg!(windows) {
+                "python.exe"
+            } else {
+                "python"
+            }),
+            Vec::new(),
+        )
+        .await;
+        assert!(super::is_python_venv(fs.as_ref(), venv).await);
+    }
+
+    #[gpui::test]
+    async fn test_inject_pth_paths(cx: &mut gpui::TestAppContext) {
+        let fs = fs::FakeFs::new(cx.executor());
+        let venv = std::path::Path::new("/venv");
+        let source_root = std::path::Path::new("/source-root");
+        let site_packages = if cfg!(windows) {
+            venv.join("Lib").join("site-packages")
+        } else {
+            venv.join("lib").join("python3.12").join("site-packages")
+        };
+        fs.create_dir(source_root).await.unwrap();
+        fs.create_dir(&site_packages.join("relative"))
+            .await
+            .unwrap();
+        fs.insert_file(
+            site_packages.join("__editable__.my_package-0.1.0.pth"),
+            format!("{}\n", source_root.display()).into_bytes(),
+        )
+        .await;
+        fs.insert_file(
+            site_packages.join("other.pth"),
+            b"# comment\n\nimport sys; sys.path.append('/nonexistent')\nrelative\n/nonexistent\n"
+                .to_vec(),
+        )
+        .await;
+        fs.insert_file(
+            site_packages.join("not_a_pth_file.txt"),
+            b"/nonexistent\n".to_vec(),
+        )
+        .await;
+
+        let pth_paths = super::venv_pth_paths(fs.as_ref(), venv).await.unwrap();
+        let mut env = HashMap::default();
+        env.insert("PYTHONPATH".to_string(), "/existing".to_string());
+        super::inject_pth_paths(&mut env, pth_paths).unwrap();
+
+        let python_paths =
+            std::env::split_paths(env.get("PYTHONPATH").unwrap()).collect::<Vec<_>>();
+        assert_eq!(
+            python_paths,
+            vec![
+                source_root.to_path_buf(),
+                site_packages.join("relative"),
+                std::path::PathBuf::from("/existing")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_tool_versions() {
+        let contents = "# pinned tools
+nodejs 18.17.0 16.0.0
+ruby   3.2.2 # trailing comment
+python system
+golang path:/opt/go
+
+erlang
+";
+        assert_eq!(
+            super::parse_tool_versions(contents).collect::<Vec<_>>(),
+            vec![
+                ("nodejs", "18.17.0"),
+                ("ruby", "3.2.2"),
+                ("golang", "path:/opt/go"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rust_toolchain_channel() {
+        assert_eq!(
+            super::rust_toolchain_channel(
+                "[toolchain]\nchannel = \"nightly-2024-01-01\"\ncomponents = [\"rustfmt\"]\n"
+            ),
+            Some("nightly-2024-01-01".to_string())
+        );
+        assert_eq!(
+            super::rust_toolchain_channel("[toolchain]\ncomponents = [\"rustfmt\"]\n"),
+            None
+        );
+        assert_eq!(
+            super::rust_toolchain_channel("channel = \"stable\"\n"),
+            None
+        );
+        assert_eq!(super::rust_toolchain_channel("not toml"), None);
+    }
+
+    #[test]
+    fn test_parse_env_file() {
+        let contents = r#"
+# A comment
+PLAIN=value
+export EXPORTED=1
+SPACED = with spaces # trailing comment
+DOUBLE="double \"quoted\"\nvalue"
+SINGLE='single # quoted'
+EMPTY=
+=no_key
+not a variable
+"#;
+        let env = super::parse_env_file(contents).collect::<HashMap<_, _>>();
+        let expected = [
+            ("PLAIN", "value"),
+            ("EXPORTED", "1"),
+            ("SPACED", "with spaces"),
+            ("DOUBLE", "double \"quoted\"\nvalue"),
+            ("SINGLE", "single # quoted"),
+            ("EMPTY", ""),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect::<HashMap<_, _>>();
+        assert_eq!(env, expected);
+    }
+
+    #[test]
+    fn test_wrap_for_ssh_task_command_ipv6_host() {
+        let ssh_command = SshCommand::new(
+            vec![
+                "-p".to_string(),
+                "2222".to_string(),
+                "user@fe80::1".to_string(),
+            ],
+            X11Forwarding::Disabled,
+        );
+        let (program, args) = super::wrap_for_ssh(
+            &ssh_command,
+            Some((
+                &"cargo".to_string(),
+                &vec!["test".to_string(), "my test".to_string()],
+            )),
+            None,
+            Some(std::path::Path::new("/srv/project")),
+            HashMap::default(),
+            &[],
+            None,
+            RemoteShell::Sh,
+        )
+        .unwrap();
+        assert_eq!(program, "ssh");
+        // The host is passed to ssh as is, without splitting the IPv6 address at its colons.
+        assert_eq!(args[..4], ["-p", "2222", "user@fe80::1", "-t"]);
+        assert_eq!(args.len(), 5);
+
+        let invocation = shlex::split(&args[4]).unwrap();
+        assert_eq!(invocation[..2], ["sh", "-c"]);
+        let commands = &invocation[2];
+        assert!(commands.starts_with("cd \"/srv/project\"; "));
+        assert!(commands.ends_with(" cargo test 'my test'"));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_activate_script_commands() {
+        use terminal::terminal_settings::ActivateScript;
+
+        let script_dir = "/home/user/my project/.venv/bin";
+        for (activate_script, script_name, expected) in [
+            (
+                ActivateScript::Default,
+                "activate",
+                "source '/home/user/my project/.venv/bin/activate'\n",
+            ),
+            (
+                ActivateScript::Csh,
+                "activate.csh",
+                "source '/home/user/my project/.venv/bin/activate.csh'\n",
+            ),
+            (
+                ActivateScript::Fish,
+                "activate.fish",
+                "source '/home/user/my project/.venv/bin/activate.fish'\n",
+            ),
+            (
+                ActivateScript::Nushell,
+                "activate.nu",
+                "overlay use `/home/user/my project/.venv/bin/activate.nu`\n",
+            ),
+            (
+                ActivateScript::PowerShell,
+                "activate.ps1",
+                ". '/home/user/my project/.venv/bin/activate.ps1'\n",
+            ),
+        ] {
+            assert_eq!(
+                super::activate_script_command(
+                    activate_script,
+                    &format!("{script_dir}/{script_name}")
+                )
+                .as_deref(),
+                Some(expected),
+                "{activate_script:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_nushell_activate_command() {
+        use terminal::terminal_settings::ActivateScript;
+
+        assert_eq!(
+            super::activate_script_command(
+                ActivateScript::Nushell,
+                "/home/user/my project/.venv/bin/activate.nu"
+            )
+            .as_deref(),
+            Some("overlay use `/home/user/my project/.venv/bin/activate.nu`\n")
+        );
+        assert_eq!(
+            super::activate_script_command(
+                ActivateScript::Nushell,
+                "/home/user/odd`name/.venv/bin/activate.nu"
+            )
+            .as_deref(),
+            Some("overlay use \"/home/user/odd`name/.venv/bin/activate.nu\"\n")
+        );
+    }
 }
@@ -860,6 +860,8 @@ impl RemoteServerProjects {
                     nickname: None,
                     args: connection_options.args.unwrap_or_default(),
                     upload_binary_over_ssh: None,
+                    remote_shell: None,
+                    x11_forwarding: None,
                 })
         });
     }
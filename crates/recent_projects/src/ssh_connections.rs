@@ -17,7 +17,7 @@ use language::CursorShape;
 use markdown::{Markdown, MarkdownStyle};
 use release_channel::ReleaseChannel;
 use remote::ssh_session::ConnectionIdentifier;
-use remote::{SshConnectionOptions, SshPlatform, SshRemoteClient};
+use remote::{RemoteShell, SshConnectionOptions, SshPlatform, SshRemoteClient, X11Forwarding};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use settings::{Settings, SettingsSources};
@@ -49,6 +49,8 @@ impl SshSettings {
                 return SshConnectionOptions {
                     nickname: conn.nickname,
                     upload_binary_over_ssh: conn.upload_binary_over_ssh.unwrap_or_default(),
+                    remote_shell: conn.remote_shell.unwrap_or_default(),
+                    x11_forwarding: conn.x11_forwarding.unwrap_or_default(),
                     args: Some(conn.args),
                     host,
                     port,
@@ -87,6 +89,14 @@ pub struct SshConnection {
     // limited outbound internet access.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub upload_binary_over_ssh: Option<bool>,
+    /// The shell to run the terminal commands with on this server: "sh", "bash", "fish" or "nushell".
+    /// Defaults to "sh".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote_shell: Option<RemoteShell>,
+    /// Whether the terminals opened on this server forward X11: "disabled", "trusted" or "untrusted".
+    /// Defaults to "disabled".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x11_forwarding: Option<X11Forwarding>,
 }
 
 impl From<SshConnection> for SshConnectionOptions {
@@ -99,6 +109,8 @@ impl From<SshConnection> for SshConnectionOptions {
             args: Some(val.args),
             nickname: val.nickname,
             upload_binary_over_ssh: val.upload_binary_over_ssh.unwrap_or_default(),
+            remote_shell: val.remote_shell.unwrap_or_default(),
+            x11_forwarding: val.x11_forwarding.unwrap_or_default(),
         }
     }
 }
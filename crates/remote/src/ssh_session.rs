@@ -69,6 +69,64 @@ pub struct SshConnectionOptions {
 
     pub nickname: Option<String>,
     pub upload_binary_over_ssh: bool,
+    pub remote_shell: RemoteShell,
+    pub x11_forwarding: X11Forwarding,
+}
+
+/// The shell used on the remote host to run the terminal commands.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+    schemars::JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum RemoteShell {
+    #[default]
+    Sh,
+    Bash,
+    Fish,
+    Nushell,
+}
+
+/// Whether the terminals opened on the remote host forward their X11 connections.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+    schemars::JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum X11Forwarding {
+    #[default]
+    Disabled,
+    /// Trusted forwarding, the remote clients get full access to the local display (`ssh -Y`).
+    Trusted,
+    /// Forwarding subject to the X11 SECURITY extension restrictions (`ssh -X`).
+    Untrusted,
+}
+
+impl X11Forwarding {
+    /// The `ssh` flag enabling this kind of forwarding, if any.
+    pub fn ssh_flag(&self) -> Option<&'static str> {
+        match self {
+            X11Forwarding::Disabled => None,
+            X11Forwarding::Trusted => Some("-Y"),
+            X11Forwarding::Untrusted => Some("-X"),
+        }
+    }
 }
 
 #[macro_export]
@@ -162,6 +220,8 @@ impl SshConnectionOptions {
             password: None,
             nickname: None,
             upload_binary_over_ssh: false,
+            remote_shell: RemoteShell::default(),
+            x11_forwarding: X11Forwarding::default(),
         })
     }
 
@@ -472,6 +532,8 @@ pub struct SshRemoteClient {
     unique_identifier: String,
     connection_options: SshConnectionOptions,
     state: Arc<Mutex<Option<State>>>,
+    /// The round-trip time of the latest heartbeat, in milliseconds.
+    rtt_ms: Option<u64>,
 }
 
 #[derive(Debug)]
@@ -536,6 +598,7 @@ impl SshRemoteClient {
                     unique_identifier: unique_identifier.clone(),
                     connection_options: connection_options.clone(),
                     state: Arc::new(Mutex::new(Some(State::Connecting))),
+                    rtt_ms: None,
                 })?;
 
                 let ssh_connection = cx
@@ -842,14 +905,21 @@ impl SshRemoteClient {
                         _ = keepalive_timer => {
                             log::debug!("Sending heartbeat to server...");
 
+                            let ping_start = Instant::now();
                             let result = select_biased! {
                                 _ = connection_activity_rx.next().fuse() => {
-                                    Ok(())
+                                    Ok(None)
                                 }
                                 ping_result = client.ping(HEARTBEAT_TIMEOUT).fuse() => {
-                                    ping_result
+                                    ping_result.map(|()| Some(ping_start.elapsed()))
                                 }
                             };
+                            if let Ok(Some(rtt)) = result {
+                                this.update(&mut cx, |this, cx| {
+                                    this.rtt_ms = Some(rtt.as_millis() as u64);
+                                    cx.notify();
+                                })?;
+                            }
 
                             if result.is_err() {
                                 missed_heartbeats += 1;
@@ -1015,6 +1085,11 @@ impl SshRemoteClient {
         self.connection_options.clone()
     }
 
+    /// The round-trip time to the server, as measured by the latest heartbeat, in milliseconds.
+    pub fn rtt_ms(&self) -> Option<u64> {
+        self.rtt_ms
+    }
+
     pub fn connection_state(&self) -> ConnectionState {
         self.state
             .lock()
@@ -13,16 +13,19 @@ use std::borrow::Cow;
 use std::path::PathBuf;
 use std::str::FromStr;
 
-pub use task_template::{HideStrategy, RevealStrategy, TaskTemplate, TaskTemplates};
+pub use task_template::{
+    HideStrategy, RevealStrategy, TaskTemplate, TaskTemplates, TestFramework, WorkingDirStrategy,
+};
 pub use vscode_format::VsCodeTaskFile;
 
 /// Task identifier, unique within the application.
 /// Based on it, task reruns and terminal tabs are managed.
-#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct TaskId(pub String);
 
 /// Contains all information needed by Zed to spawn a new terminal tab for the given task.
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Serializable, so that the task terminals can be restored later.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SpawnInTerminal {
     /// Id of the task to use when determining task tab affinity.
     pub id: TaskId,
@@ -39,6 +42,10 @@ pub struct SpawnInTerminal {
     pub command_label: String,
     /// Current working directory to spawn the command into.
     pub cwd: Option<PathBuf>,
+    /// How to show the working directory to the user, if not as `cwd`:
+    /// e.g. the remote path a remote task runs in, rather than the local workspace path.
+    #[serde(default)]
+    pub cwd_label: Option<String>,
     /// Env overrides for the command, will be appended to the terminal's environment from the settings.
     pub env: HashMap<String, String>,
     /// Whether to use a new terminal tab or reuse the existing one to spawn the process.
@@ -55,6 +62,25 @@ pub struct SpawnInTerminal {
     pub show_summary: bool,
     /// Whether to show the command line in the task output.
     pub show_command: bool,
+    /// The maximum number of output lines to show in the task's terminal, if any.
+    pub max_output_lines: Option<usize>,
+    /// The scrollback size of the task's terminal, instead of the maximum one.
+    #[serde(default)]
+    pub override_max_scroll_history_lines: Option<usize>,
+    /// The group of related tasks this task belongs to, if any.
+    pub group_id: Option<String>,
+    /// The test runner the task runs, if any.
+    pub test_framework: Option<TestFramework>,
+    /// Whether the task runs an interactive full-screen program, whose output should not be
+    /// interleaved with the task summary and command lines.
+    pub interactive_tui: bool,
+    /// Whether to spawn the task again, when its process gets killed by a crash signal.
+    pub restart_on_crash: bool,
+    /// How many times in a row a crashed task is restarted, before giving up.
+    pub max_restarts: usize,
+    /// How to pick the directory to run the task in, overriding `cwd` when it resolves to one.
+    #[serde(default)]
+    pub working_dir_strategy: Option<WorkingDirStrategy>,
 }
 
 /// A final form of the [`TaskTemplate`], that got resolved with a particualar [`TaskContext`] and now is ready to spawn the actual task.
@@ -276,4 +302,10 @@ pub enum Shell {
         /// An optional string to override the title of the terminal tab
         title_override: Option<SharedString>,
     },
+    /// Use a login shell (started with `-l`), that reads the login startup files, e.g. `~/.zprofile`.
+    Login {
+        /// The shell program to run, the one from `$SHELL` if not specified.
+        #[serde(default)]
+        program: Option<String>,
+    },
 }
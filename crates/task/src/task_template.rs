@@ -64,6 +64,36 @@ pub struct TaskTemplate {
     /// Whether to show the command line in the task output.
     #[serde(default = "default_true")]
     pub show_command: bool,
+    /// The maximum number of output lines to show for the task.
+    /// When the limit is reached, the terminal stops showing the output, but the task keeps running.
+    #[serde(default)]
+    pub max_output_lines: Option<usize>,
+    /// The scrollback size of the task's terminal, instead of the maximum one.
+    #[serde(default)]
+    pub max_scroll_history_lines: Option<usize>,
+    /// An optional group name, used to visually group related tasks' terminals together.
+    #[serde(default)]
+    pub group_id: Option<String>,
+    /// The test runner the task runs, if any, to report the individual test results from the task output.
+    #[serde(default)]
+    pub test_framework: Option<TestFramework>,
+    /// Whether the task runs an interactive full-screen program (e.g. `htop` or `lazygit`).
+    /// Such tasks never show the summary and command lines, regardless of `show_summary` and `show_command`.
+    #[serde(default)]
+    pub interactive_tui: bool,
+    /// Whether to spawn the task again, when its process gets killed by a crash signal (e.g. `SIGSEGV` or `SIGABRT`).
+    #[serde(default)]
+    pub restart_on_crash: bool,
+    /// How many times in a row a crashed task is restarted, before giving up.
+    #[serde(default = "default_max_restarts")]
+    pub max_restarts: usize,
+    /// How to pick the directory to run the task in, instead of `cwd`, when the task is spawned.
+    #[serde(default)]
+    pub working_dir_strategy: Option<WorkingDirStrategy>,
+}
+
+fn default_max_restarts() -> usize {
+    3
 }
 
 /// What to do with the terminal pane and tab, after the command was started.
@@ -79,6 +109,21 @@ pub enum RevealStrategy {
     Never,
 }
 
+/// How to pick the directory to run a task in, when the task is spawned.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkingDirStrategy {
+    /// The root of the worktree of the current file.
+    WorkspaceRoot,
+    /// The closest directory containing a file with the given name (e.g. `package.json`),
+    /// from the directory of the current file up to its worktree root.
+    NearestAncestorWithFile(String),
+    /// The given directory.
+    Explicit(PathBuf),
+    /// The directory of the current file.
+    CurrentFileDir,
+}
+
 /// What to do with the terminal pane and tab, after the command has finished.
 #[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -92,6 +137,20 @@ pub enum HideStrategy {
     OnSuccess,
 }
 
+/// A test runner, which output can be parsed into individual test results.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TestFramework {
+    /// `cargo test`
+    Cargo,
+    /// `pytest -v`
+    Pytest,
+    /// `jest` or `mocha`
+    Jest,
+    /// `go test -v`
+    Go,
+}
+
 /// A group of Tasks defined in a JSON file.
 #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct TaskTemplates(pub Vec<TaskTemplate>);
@@ -219,6 +278,7 @@ impl TaskTemplate {
             resolved: Some(SpawnInTerminal {
                 id,
                 cwd,
+                cwd_label: None,
                 full_label,
                 label: human_readable_label,
                 command_label: args_with_substitutions.iter().fold(
@@ -239,6 +299,14 @@ impl TaskTemplate {
                 shell: self.shell.clone(),
                 show_summary: self.show_summary,
                 show_command: self.show_command,
+                max_output_lines: self.max_output_lines,
+                override_max_scroll_history_lines: self.max_scroll_history_lines,
+                group_id: self.group_id.clone(),
+                test_framework: self.test_framework,
+                interactive_tui: self.interactive_tui,
+                restart_on_crash: self.restart_on_crash,
+                max_restarts: self.max_restarts,
+                working_dir_strategy: self.working_dir_strategy.clone(),
             }),
         })
     }
@@ -815,4 +883,31 @@ mod tests {
             "overwritten"
         );
     }
+
+    #[test]
+    fn test_resolved_task_serialization_roundtrip() {
+        let template = TaskTemplate {
+            label: "Run $ZED_FILE".to_string(),
+            command: "echo".to_string(),
+            args: vec!["$ZED_FILE".to_string()],
+            env: HashMap::from_iter([("KEY".to_string(), "value".to_string())]),
+            shell: Shell::Program("bash".to_string()),
+            group_id: Some("group".to_string()),
+            ..TaskTemplate::default()
+        };
+        let context = TaskContext {
+            cwd: Some(PathBuf::from("/dir")),
+            task_variables: TaskVariables::from_iter([(VariableName::File, "a.rs".to_string())]),
+            project_env: HashMap::default(),
+        };
+        let resolved = template
+            .resolve_task(TEST_ID_BASE, &context)
+            .unwrap()
+            .resolved
+            .unwrap();
+
+        let serialized = serde_json_lenient::to_string(&resolved).unwrap();
+        let deserialized: SpawnInTerminal = serde_json_lenient::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, resolved);
+    }
 }
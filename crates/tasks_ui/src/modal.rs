@@ -1,6 +1,7 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use crate::active_item_selection_properties;
+use collections::HashMap;
 use fuzzy::{StringMatch, StringMatchCandidate};
 use gpui::{
     rems, Action, AnyElement, AppContext, DismissEvent, EventEmitter, FocusableView,
@@ -9,12 +10,12 @@ use gpui::{
 };
 use picker::{highlighted_match_with_paths::HighlightedText, Picker, PickerDelegate};
 use project::{task_store::TaskStore, TaskSourceKind};
-use task::{ResolvedTask, TaskContext, TaskTemplate};
+use task::{ResolvedTask, TaskContext, TaskId, TaskTemplate};
 use ui::{
     div, h_flex, v_flex, ActiveTheme, Button, ButtonCommon, ButtonSize, Clickable, Color,
     FluentBuilder as _, Icon, IconButton, IconButtonShape, IconName, IconSize, IntoElement,
-    KeyBinding, LabelSize, ListItem, ListItemSpacing, RenderOnce, Selectable, Tooltip,
-    WindowContext,
+    KeyBinding, Label, LabelCommon, LabelSize, ListItem, ListItemSpacing, RenderOnce, Selectable,
+    Tooltip, WindowContext,
 };
 use util::ResultExt;
 use workspace::{tasks::schedule_resolved_task, ModalView, Workspace};
@@ -24,6 +25,8 @@ pub use zed_actions::{Rerun, Spawn};
 pub(crate) struct TasksModalDelegate {
     task_store: Model<TaskStore>,
     candidates: Option<Vec<(TaskSourceKind, ResolvedTask)>>,
+    /// Average durations of the recent runs of the candidates that were run before.
+    average_run_durations: HashMap<TaskId, Duration>,
     last_used_candidate_index: Option<usize>,
     divider_index: Option<usize>,
     matches: Vec<StringMatch>,
@@ -44,6 +47,7 @@ impl TasksModalDelegate {
             task_store,
             workspace,
             candidates: None,
+            average_run_durations: HashMap::default(),
             matches: Vec::new(),
             last_used_candidate_index: None,
             divider_index: None,
@@ -163,17 +167,17 @@ impl PickerDelegate for TasksModalDelegate {
         cx: &mut ViewContext<picker::Picker<Self>>,
     ) -> Task<()> {
         cx.spawn(move |picker, mut cx| async move {
-            let Some(candidates) = picker
+            let Some((candidates, average_run_durations)) = picker
                 .update(&mut cx, |picker, cx| {
                     match &mut picker.delegate.candidates {
-                        Some(candidates) => string_match_candidates(candidates.iter()),
+                        Some(candidates) => (string_match_candidates(candidates.iter()), None),
                         None => {
                             let Ok((worktree, location)) =
                                 picker.delegate.workspace.update(cx, |workspace, cx| {
                                     active_item_selection_properties(workspace, cx)
                                 })
                             else {
-                                return Vec::new();
+                                return (Vec::new(), None);
                             };
                             let Some(task_inventory) = picker
                                 .delegate
@@ -182,7 +186,7 @@ impl PickerDelegate for TasksModalDelegate {
                                 .task_inventory()
                                 .cloned()
                             else {
-                                return Vec::new();
+                                return (Vec::new(), None);
                             };
 
                             let (used, current) =
@@ -200,9 +204,16 @@ impl PickerDelegate for TasksModalDelegate {
 
                             let mut new_candidates = used;
                             new_candidates.extend(current);
+                            let average_run_durations = picker
+                                .delegate
+                                .workspace
+                                .update(cx, |workspace, cx| {
+                                    workspace.project().read(cx).average_task_run_durations(cx)
+                                })
+                                .ok();
                             let match_candidates = string_match_candidates(new_candidates.iter());
                             let _ = picker.delegate.candidates.insert(new_candidates);
-                            match_candidates
+                            (match_candidates, average_run_durations)
                         }
                     }
                 })
@@ -219,10 +230,24 @@ impl PickerDelegate for TasksModalDelegate {
                 cx.background_executor().clone(),
             )
             .await;
+            let average_run_durations = match average_run_durations {
+                Some(average_run_durations) => Some(average_run_durations.await),
+                None => None,
+            };
             picker
                 .update(&mut cx, |picker, _| {
                     let delegate = &mut picker.delegate;
+                    if let Some(average_run_durations) = average_run_durations {
+                        delegate.average_run_durations = average_run_durations;
+                    }
                     delegate.matches = matches;
+                    if let Some(candidates) = delegate.candidates.as_ref() {
+                        rank_and_relabel_matches(
+                            &mut delegate.matches,
+                            candidates,
+                            !query.is_empty(),
+                        );
+                    }
                     if let Some(index) = delegate.last_used_candidate_index {
                         delegate.matches.sort_by_key(|m| m.candidate_id > index);
                     }
@@ -306,6 +331,7 @@ impl PickerDelegate for TasksModalDelegate {
             Some(Tooltip::text(tooltip_label_text, cx))
         };
 
+        let average_run_duration = self.average_run_durations.get(&resolved_task.id).copied();
         let highlighted_location = HighlightedText {
             text: hit.string.clone(),
             highlight_positions: hit.positions.clone(),
@@ -380,7 +406,18 @@ impl PickerDelegate for TasksModalDelegate {
                     item
                 })
                 .selected(selected)
-                .child(highlighted_location.render(cx)),
+                .child(
+                    h_flex()
+                        .gap_2()
+                        .child(highlighted_location.render(cx))
+                        .when_some(average_run_duration, |this, duration| {
+                            this.child(
+                                Label::new(format_run_duration(duration))
+                                    .size(LabelSize::Small)
+                                    .color(Color::Muted),
+                            )
+                        }),
+                ),
         )
     }
 
@@ -516,11 +553,64 @@ fn string_match_candidates<'a>(
         .map(|(index, (_, candidate))| StringMatchCandidate {
             id: index,
             char_bag: candidate.resolved_label.chars().collect(),
-            string: candidate.display_label().to_owned(),
+            string: candidate.resolved_label.clone(),
         })
         .collect()
 }
 
+/// How much a fuzzy match score is boosted for tasks, provided by the language of the current file.
+const LANGUAGE_TASK_SCORE_BOOST: f64 = 1.2;
+
+/// Tasks are matched against their full labels (e.g. with namespaces and non-truncated variables),
+/// but shown with their human readable labels: swap the matched strings back and keep the highlights
+/// that still point into the shared prefix of both labels.
+///
+/// When the query is not empty, tasks from the current file's language are ranked higher.
+fn rank_and_relabel_matches(
+    matches: &mut [StringMatch],
+    candidates: &[(TaskSourceKind, ResolvedTask)],
+    rank_by_score: bool,
+) {
+    for hit in matches.iter_mut() {
+        let Some((source_kind, task)) = candidates.get(hit.candidate_id) else {
+            continue;
+        };
+        if rank_by_score && matches!(source_kind, TaskSourceKind::Language { .. }) {
+            hit.score *= LANGUAGE_TASK_SCORE_BOOST;
+        }
+        let display_label = task.display_label();
+        if hit.string != display_label {
+            let common_prefix_len = hit
+                .string
+                .char_indices()
+                .zip(display_label.chars())
+                .find(|((_, full_char), display_char)| full_char != display_char)
+                .map_or_else(
+                    || hit.string.len().min(display_label.len()),
+                    |((ix, _), _)| ix,
+                );
+            hit.positions
+                .retain(|&position| position < common_prefix_len);
+            hit.string = display_label.to_owned();
+        }
+    }
+    if rank_by_score {
+        matches.sort_by(|a, b| b.cmp(a));
+    }
+}
+
+fn format_run_duration(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    if millis < 1000 {
+        format!("~{millis}ms")
+    } else if millis < 60_000 {
+        format!("~{:.1}s", duration.as_secs_f64())
+    } else {
+        let secs = duration.as_secs();
+        format!("~{}m {}s", secs / 60, secs % 60)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::{path::PathBuf, sync::Arc};
@@ -537,6 +627,13 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_format_run_duration() {
+        assert_eq!(format_run_duration(Duration::from_millis(350)), "~350ms");
+        assert_eq!(format_run_duration(Duration::from_millis(1300)), "~1.3s");
+        assert_eq!(format_run_duration(Duration::from_secs(125)), "~2m 5s");
+    }
+
     #[gpui::test]
     async fn test_spawn_tasks_modal_query_reuse(cx: &mut TestAppContext) {
         init_test(cx);
@@ -103,6 +103,7 @@ pub enum Event {
     Edit(EditEvent),
     Action(ActionEvent),
     Repl(ReplEvent),
+    Task(TaskEvent),
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -207,6 +208,14 @@ pub struct ReplEvent {
     pub repl_session_id: String,
 }
 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TaskEvent {
+    /// The language of the file the task was launched for, if any
+    pub language: Option<String>,
+    /// Whether the task runs on a remote host via SSH
+    pub is_remote: bool,
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct BacktraceFrame {
     pub ip: usize,
@@ -0,0 +1,106 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+/// The output line limit of a task, shared by its terminal and the thread reading its pseudoterminal.
+#[derive(Debug)]
+pub(crate) struct OutputLimit {
+    max_lines: usize,
+    /// The output lines shown by the terminal, since the task started or the scrollback was cleared.
+    lines: AtomicUsize,
+    truncated: AtomicBool,
+    lifted: AtomicBool,
+    /// The whole output of the task, including the part the terminal has not shown.
+    full_output_path: PathBuf,
+}
+
+impl OutputLimit {
+    pub(crate) fn new(max_lines: usize) -> Self {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+        let file_name = format!(
+            "zed-task-output-{}-{}.log",
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        );
+        Self {
+            max_lines,
+            lines: AtomicUsize::new(0),
+            truncated: AtomicBool::new(false),
+            lifted: AtomicBool::new(false),
+            full_output_path: std::env::temp_dir().join(file_name),
+        }
+    }
+
+    pub(crate) fn is_truncated(&self) -> bool {
+        self.truncated.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn full_output_path(&self) -> &Path {
+        &self.full_output_path
+    }
+
+    /// Lets all further output reach the terminal.
+    pub(crate) fn lift(&self) {
+        self.lifted.store(true, Ordering::Release);
+        self.truncated.store(false, Ordering::Release);
+    }
+
+    /// Counts the output lines anew, e.g. after the terminal's scrollback was cleared.
+    pub(crate) fn reset(&self) {
+        self.lines.store(0, Ordering::Release);
+        self.truncated.store(false, Ordering::Release);
+    }
+
+    /// How many leading bytes of the output the terminal may show, within the line limit.
+    pub(crate) fn allowed_len(&self, output: &[u8]) -> usize {
+        if self.lifted.load(Ordering::Acquire) {
+            return output.len();
+        }
+        let mut lines = self.lines.load(Ordering::Acquire);
+        let mut allowed_len = 0;
+        while allowed_len < output.len() && lines < self.max_lines {
+            match output[allowed_len..].iter().position(|byte| *byte == b'\n') {
+                Some(newline_ix) => {
+                    allowed_len += newline_ix + 1;
+                    lines += 1;
+                }
+                None => allowed_len = output.len(),
+            }
+        }
+        self.lines.store(lines, Ordering::Release);
+        if allowed_len < output.len() {
+            self.truncated.store(true, Ordering::Release);
+        }
+        allowed_len
+    }
+}
+
+impl Drop for OutputLimit {
+    fn drop(&mut self) {
+        std::fs::remove_file(&self.full_output_path).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OutputLimit;
+
+    #[test]
+    fn test_output_limit_allowed_len() {
+        let limit = OutputLimit::new(2);
+        assert_eq!(limit.allowed_len(b"one\ntw"), 6);
+        assert!(!limit.is_truncated());
+        assert_eq!(limit.allowed_len(b"o\nthree\nfour\n"), 2);
+        assert!(limit.is_truncated());
+        assert_eq!(limit.allowed_len(b"five\n"), 0);
+
+        limit.reset();
+        assert!(!limit.is_truncated());
+        assert_eq!(limit.allowed_len(b"six\n"), 4);
+
+        limit.lift();
+        assert_eq!(limit.allowed_len(b"seven\neight\nnine\n"), 17);
+        assert!(!limit.is_truncated());
+    }
+}
@@ -75,7 +75,8 @@ pub struct ProcessInfo {
 pub struct PtyProcessInfo {
     system: System,
     refresh_kind: ProcessRefreshKind,
-    pid_getter: ProcessIdGetter,
+    /// `None` for the terminals without a process, e.g. the ones on a fake PTY.
+    pid_getter: Option<ProcessIdGetter>,
     pub current: Option<ProcessInfo>,
 }
 
@@ -91,13 +92,28 @@ impl PtyProcessInfo {
         PtyProcessInfo {
             system,
             refresh_kind: process_refresh_kind,
-            pid_getter: ProcessIdGetter::new(pty),
+            pid_getter: Some(ProcessIdGetter::new(pty)),
+            current: None,
+        }
+    }
+
+    /// The id of the terminal's foreground process group, or of its shell if that cannot be determined.
+    pub fn pid(&self) -> Option<Pid> {
+        self.pid_getter.as_ref()?.pid()
+    }
+
+    /// The info of a terminal without a process, which never changes.
+    pub fn without_process() -> PtyProcessInfo {
+        PtyProcessInfo {
+            system: System::new(),
+            refresh_kind: ProcessRefreshKind::new(),
+            pid_getter: None,
             current: None,
         }
     }
 
     fn refresh(&mut self) -> Option<&Process> {
-        let pid = self.pid_getter.pid()?;
+        let pid = self.pid_getter.as_ref()?.pid()?;
         if self.system.refresh_processes_specifics(
             sysinfo::ProcessesToUpdate::Some(&[pid]),
             self.refresh_kind,
@@ -0,0 +1,247 @@
+use alacritty_terminal::{
+    event::{Event as AlacTermEvent, EventListener, OnResize, WindowSize},
+    tty::{ChildEvent, EventedPty, EventedReadWrite, Pty},
+};
+use polling::{Event, PollMode, Poller};
+use std::{
+    fs::File,
+    io::{self, Read, Write},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+use util::ResultExt;
+
+use crate::{output_limit::OutputLimit, ZedListener};
+
+/// The pseudoterminal of a terminal, with the output processing alacritty does not do:
+/// keeping the output beyond a task's [`OutputLimit`] from the terminal,
+/// and tracking the working directory the shell reports with OSC 7.
+pub(crate) struct ProcessedPty {
+    pty: Pty,
+    limit: Option<Arc<OutputLimit>>,
+    /// Created on the first output, `None` inside if that failed.
+    full_output: Option<Option<File>>,
+    working_directory: Option<(Osc7Scanner, Arc<Mutex<Option<PathBuf>>>)>,
+    events: ZedListener,
+}
+
+impl ProcessedPty {
+    pub(crate) fn new(pty: Pty, events: ZedListener) -> Self {
+        Self {
+            pty,
+            limit: None,
+            full_output: None,
+            working_directory: None,
+            events,
+        }
+    }
+
+    /// Stops the output from reaching the terminal past the limit. That output is still read,
+    /// so the task never blocks on it, and written into the full output file only.
+    pub(crate) fn with_output_limit(mut self, limit: Option<Arc<OutputLimit>>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Stores the working directories reported with OSC 7 into `working_directory`.
+    pub(crate) fn with_reported_working_directory(
+        mut self,
+        working_directory: Option<Arc<Mutex<Option<PathBuf>>>>,
+    ) -> Self {
+        self.working_directory =
+            working_directory.map(|working_directory| (Osc7Scanner::default(), working_directory));
+        self
+    }
+
+    fn save_full_output(&mut self, output: &[u8]) {
+        let Some(limit) = &self.limit else {
+            return;
+        };
+        let full_output = self
+            .full_output
+            .get_or_insert_with(|| File::create(limit.full_output_path()).log_err());
+        if let Some(file) = full_output {
+            if file.write_all(output).log_err().is_none() {
+                *full_output = None;
+            }
+        }
+    }
+
+    fn track_working_directory(&mut self, output: &[u8]) {
+        let Some((scanner, working_directory)) = &mut self.working_directory else {
+            return;
+        };
+        if let Some(reported) = scanner.scan(output) {
+            if let Ok(mut working_directory) = working_directory.lock() {
+                *working_directory = Some(reported);
+            }
+        }
+    }
+}
+
+impl Read for ProcessedPty {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let read = self.pty.reader().read(buf)?;
+            self.track_working_directory(&buf[..read]);
+            let Some(limit) = &self.limit else {
+                return Ok(read);
+            };
+            let was_truncated = limit.is_truncated();
+            let allowed_len = limit.allowed_len(&buf[..read]);
+            let truncated = !was_truncated && limit.is_truncated();
+            self.save_full_output(&buf[..read]);
+            if allowed_len > 0 || read == 0 {
+                return Ok(allowed_len);
+            }
+            // Nothing reaches the terminal to wake it up, so tell it about the truncation directly.
+            if truncated {
+                self.events.send_event(AlacTermEvent::Wakeup);
+            }
+            // Keep draining the task's output, until the pseudoterminal has nothing more to read.
+        }
+    }
+}
+
+impl Write for ProcessedPty {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pty.writer().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.pty.writer().flush()
+    }
+}
+
+impl EventedReadWrite for ProcessedPty {
+    type Reader = Self;
+    type Writer = Self;
+
+    unsafe fn register(
+        &mut self,
+        poll: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.pty.register(poll, interest, mode)
+    }
+
+    fn reregister(
+        &mut self,
+        poll: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.pty.reregister(poll, interest, mode)
+    }
+
+    fn deregister(&mut self, poll: &Arc<Poller>) -> io::Result<()> {
+        self.pty.deregister(poll)
+    }
+
+    fn reader(&mut self) -> &mut Self::Reader {
+        self
+    }
+
+    fn writer(&mut self) -> &mut Self::Writer {
+        self
+    }
+}
+
+impl EventedPty for ProcessedPty {
+    fn next_child_event(&mut self) -> Option<ChildEvent> {
+        self.pty.next_child_event()
+    }
+}
+
+impl OnResize for ProcessedPty {
+    fn on_resize(&mut self, window_size: WindowSize) {
+        self.pty.on_resize(window_size)
+    }
+}
+
+const OSC7_PREFIX: &[u8] = b"\x1b]7;";
+/// Longer sequences are not working directory reports, but garbage.
+const OSC7_MAX_LEN: usize = 4096;
+
+/// Finds the working directories reported with `OSC 7 ; file://host/path`, terminated with BEL or ST,
+/// also when a sequence is split between reads.
+#[derive(Default)]
+struct Osc7Scanner {
+    /// How many bytes of [`OSC7_PREFIX`] have been matched.
+    matched_prefix: usize,
+    /// The payload of the sequence, once the whole prefix has been matched.
+    payload: Option<Vec<u8>>,
+}
+
+impl Osc7Scanner {
+    /// Returns the last working directory reported in the output.
+    fn scan(&mut self, output: &[u8]) -> Option<PathBuf> {
+        let mut reported = None;
+        for &byte in output {
+            if let Some(payload) = &mut self.payload {
+                match byte {
+                    // BEL, or the ESC starting ST.
+                    b'\x07' | b'\x1b' => {
+                        reported = parse_osc7_payload(payload).or(reported);
+                        self.payload = None;
+                    }
+                    _ if payload.len() >= OSC7_MAX_LEN => self.payload = None,
+                    _ => payload.push(byte),
+                }
+            } else if byte == OSC7_PREFIX[self.matched_prefix] {
+                self.matched_prefix += 1;
+                if self.matched_prefix == OSC7_PREFIX.len() {
+                    self.matched_prefix = 0;
+                    self.payload = Some(Vec::new());
+                }
+            } else {
+                self.matched_prefix = usize::from(byte == OSC7_PREFIX[0]);
+            }
+        }
+        reported
+    }
+}
+
+/// Extracts the percent-decoded path from a `file://host/path` URL.
+fn parse_osc7_payload(payload: &[u8]) -> Option<PathBuf> {
+    let url = payload.strip_prefix(b"file://")?;
+    let path = &url[url.iter().position(|byte| *byte == b'/')?..];
+    let mut decoded = Vec::with_capacity(path.len());
+    let mut bytes = path.iter();
+    while let Some(&byte) = bytes.next() {
+        if byte == b'%' {
+            let hex = [*bytes.next()?, *bytes.next()?];
+            decoded.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            decoded.push(byte);
+        }
+    }
+    String::from_utf8(decoded).ok().map(PathBuf::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Osc7Scanner;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_osc7_scanner() {
+        let mut scanner = Osc7Scanner::default();
+        assert_eq!(
+            scanner.scan(b"ls\r\n\x1b]7;file://remote-host/home/me/my%20project\x07$ "),
+            Some(PathBuf::from("/home/me/my project"))
+        );
+        assert_eq!(scanner.scan(b"plain output\r\n"), None);
+
+        assert_eq!(scanner.scan(b"\x1b]7;file://remote-host/tm"), None);
+        assert_eq!(scanner.scan(b"p\x1b\\$ "), Some(PathBuf::from("/tmp")));
+
+        assert_eq!(
+            scanner
+                .scan(b"\x1b]7;file://a/first\x07\x1b]0;title\x07\x1b\x1b]7;file://a/second\x07"),
+            Some(PathBuf::from("/second"))
+        );
+        assert_eq!(scanner.scan(b"\x1b]7;not a url\x07"), None);
+    }
+}
@@ -2,8 +2,11 @@ pub mod mappings;
 
 pub use alacritty_terminal;
 
+mod output_limit;
 mod pty_info;
+mod pty_output;
 pub mod terminal_settings;
+pub mod test_output;
 
 use alacritty_terminal::{
     event::{Event as AlacTermEvent, EventListener, Notify, WindowSize},
@@ -13,7 +16,7 @@ use alacritty_terminal::{
     selection::{Selection, SelectionRange, SelectionType},
     sync::FairMutex,
     term::{
-        cell::Cell,
+        cell::{Cell, Flags},
         search::{Match, RegexIter, RegexSearch},
         Config, RenderableCursor, TermMode,
     },
@@ -24,7 +27,7 @@ use alacritty_terminal::{
     },
     Term,
 };
-use anyhow::{bail, Result};
+use anyhow::{anyhow, Context as _, Result};
 
 use futures::{
     channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender},
@@ -38,22 +41,27 @@ use mappings::mouse::{
 
 use collections::{HashMap, VecDeque};
 use futures::StreamExt;
+use output_limit::OutputLimit;
 use pty_info::PtyProcessInfo;
+use pty_output::ProcessedPty;
 use serde::{Deserialize, Serialize};
 use settings::Settings;
 use smol::channel::{Receiver, Sender};
-use task::{HideStrategy, Shell, TaskId};
+use task::{HideStrategy, Shell, SpawnInTerminal, TaskId, TestFramework};
 use terminal_settings::{AlternateScroll, CursorShape, TerminalSettings};
+use test_output::TestResult;
 use theme::{ActiveTheme, Theme};
-use util::{paths::home_dir, truncate_and_trailoff};
+use util::{paths::home_dir, truncate_and_trailoff, ResultExt};
 
 use std::{
+    borrow::Cow,
     cmp::{self, min},
     fmt::Display,
+    io::{BufWriter, Cursor, Write as _},
     ops::{Deref, Index, RangeInclusive},
-    path::PathBuf,
-    sync::Arc,
-    time::Duration,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
 };
 use thiserror::Error;
 
@@ -69,6 +77,7 @@ actions!(
     terminal,
     [
         Clear,
+        ClearScrollback,
         Copy,
         Paste,
         ShowCharacterPalette,
@@ -80,6 +89,11 @@ actions!(
         ScrollToTop,
         ScrollToBottom,
         ToggleViMode,
+        ShowFullOutput,
+        ShowOutputDiff,
+        ExportScrollback,
+        FocusNextFailed,
+        FocusNextRunning,
     ]
 );
 
@@ -108,6 +122,12 @@ pub enum Event {
     SelectionsChanged,
     NewNavigationTarget(Option<MaybeNavigationTarget>),
     Open(MaybeNavigationTarget),
+    /// The task, running in the terminal, has finished and will produce no more output.
+    TaskFinished,
+    /// A test result, reported in the output of the task running the tests.
+    TestResult(TestResult),
+    /// The connection of the SSH terminal has been lost, rather than closed by the user.
+    SshDisconnected,
 }
 
 #[derive(Clone, Debug)]
@@ -133,6 +153,7 @@ pub enum MaybeNavigationTarget {
 enum InternalEvent {
     Resize(TerminalSize),
     Clear,
+    ClearScrollback,
     // FocusNextMatch,
     Scroll(AlacScroll),
     ScrollToAlacPoint(AlacPoint),
@@ -243,78 +264,188 @@ impl Dimensions for TerminalSize {
     }
 }
 
+/// Why a terminal could not be started.
 #[derive(Error, Debug)]
-pub struct TerminalError {
-    pub directory: Option<PathBuf>,
-    pub shell: Shell,
-    pub source: std::io::Error,
+pub enum TerminalError {
+    /// The working directory does not exist, or is not a directory.
+    InvalidWorkingDirectory(PathBuf),
+    /// The shell program could not be found.
+    ShellNotFound(PathBuf),
+    /// The pseudoterminal could not be created, or the shell could not be started in it.
+    PtyCreationFailed {
+        directory: Option<PathBuf>,
+        shell: Shell,
+        source: std::io::Error,
+    },
 }
 
-impl TerminalError {
-    pub fn fmt_directory(&self) -> String {
-        self.directory
-            .clone()
-            .map(|path| {
-                match path
-                    .into_os_string()
-                    .into_string()
-                    .map_err(|os_str| format!("<non-utf8 path> {}", os_str.to_string_lossy()))
-                {
-                    Ok(s) => s,
-                    Err(s) => s,
-                }
-            })
-            .unwrap_or_else(|| {
-                let default_dir =
-                    dirs::home_dir().map(|buf| buf.into_os_string().to_string_lossy().to_string());
-                match default_dir {
-                    Some(dir) => format!("<none specified, using home directory> {}", dir),
-                    None => "<none specified, could not find home directory>".to_string(),
-                }
-            })
-    }
+/// Signals cannot be sent to the terminal's processes on this platform.
+#[derive(Error, Debug)]
+#[error("sending signals is not supported on this platform")]
+pub struct UnsupportedOnPlatform;
+
+/// The signals commonly sent to a terminal's foreground process, with their names.
+#[cfg(unix)]
+pub const COMMON_SIGNALS: &[(&str, i32)] = &[
+    ("SIGINT", libc::SIGINT),
+    ("SIGTSTP", libc::SIGTSTP),
+    ("SIGCONT", libc::SIGCONT),
+    ("SIGHUP", libc::SIGHUP),
+    ("SIGUSR1", libc::SIGUSR1),
+    ("SIGUSR2", libc::SIGUSR2),
+    ("SIGTERM", libc::SIGTERM),
+    ("SIGKILL", libc::SIGKILL),
+];
+#[cfg(not(unix))]
+pub const COMMON_SIGNALS: &[(&str, i32)] = &[];
+
+fn fmt_directory(directory: &Option<PathBuf>) -> String {
+    directory
+        .clone()
+        .map(|path| {
+            match path
+                .into_os_string()
+                .into_string()
+                .map_err(|os_str| format!("<non-utf8 path> {}", os_str.to_string_lossy()))
+            {
+                Ok(s) => s,
+                Err(s) => s,
+            }
+        })
+        .unwrap_or_else(|| {
+            let default_dir =
+                dirs::home_dir().map(|buf| buf.into_os_string().to_string_lossy().to_string());
+            match default_dir {
+                Some(dir) => format!("<none specified, using home directory> {}", dir),
+                None => "<none specified, could not find home directory>".to_string(),
+            }
+        })
+}
 
-    pub fn fmt_shell(&self) -> String {
-        match &self.shell {
-            Shell::System => "<system defined shell>".to_string(),
-            Shell::Program(s) => s.to_string(),
-            Shell::WithArguments {
-                program,
-                args,
-                title_override,
-            } => {
-                if let Some(title_override) = title_override {
-                    format!("{} {} ({})", program, args.join(" "), title_override)
-                } else {
-                    format!("{} {}", program, args.join(" "))
-                }
+fn fmt_shell(shell: &Shell) -> String {
+    match shell {
+        Shell::System => "<system defined shell>".to_string(),
+        Shell::Program(s) => s.to_string(),
+        Shell::WithArguments {
+            program,
+            args,
+            title_override,
+        } => {
+            if let Some(title_override) = title_override {
+                format!("{} {} ({})", program, args.join(" "), title_override)
+            } else {
+                format!("{} {}", program, args.join(" "))
             }
         }
+        Shell::Login { program } => {
+            format!("{} -l", program.as_deref().unwrap_or("<login shell>"))
+        }
+    }
+}
+
+/// The program that the shell is started with, if it is known before starting it.
+fn shell_program(shell: &Shell) -> Option<&str> {
+    match shell {
+        Shell::System | Shell::Login { program: None } => None,
+        Shell::Program(program)
+        | Shell::WithArguments { program, .. }
+        | Shell::Login {
+            program: Some(program),
+        } => Some(program.as_str()),
     }
 }
 
 impl Display for TerminalError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let dir_string: String = self.fmt_directory();
-        let shell = self.fmt_shell();
-
-        write!(
-            f,
-            "Working directory: {} Shell command: `{}`, IOError: {}",
-            dir_string, shell, self.source
-        )
+        match self {
+            TerminalError::InvalidWorkingDirectory(directory) => write!(
+                f,
+                "Working directory {} does not exist or is not a directory. \
+                Check the `working_directory` terminal setting, or the `cwd` of the task",
+                directory.display()
+            ),
+            TerminalError::ShellNotFound(program) => write!(
+                f,
+                "Shell `{}` was not found. \
+                Check the `shell` terminal setting, or that the program is installed and in PATH",
+                program.display()
+            ),
+            TerminalError::PtyCreationFailed {
+                directory,
+                shell,
+                source,
+            } => write!(
+                f,
+                "Failed to start a pseudoterminal. Working directory: {} Shell command: `{}`, \
+                IOError: {}. Check that pseudoterminals are available on the system (e.g. /dev/ptmx) \
+                and that the limit of open files (`ulimit -n`) is not reached",
+                fmt_directory(directory),
+                fmt_shell(shell),
+                source
+            ),
+        }
     }
 }
 
 // https://github.com/alacritty/alacritty/blob/cb3a79dbf6472740daca8440d5166c1d4af5029e/extra/man/alacritty.5.scd?plain=1#L207-L213
 const DEFAULT_SCROLL_HISTORY_LINES: usize = 10_000;
-const MAX_SCROLL_HISTORY_LINES: usize = 100_000;
+pub const MAX_SCROLL_HISTORY_LINES: usize = 100_000;
 const URL_REGEX: &str = r#"(ipfs:|ipns:|magnet:|mailto:|gemini://|gopher://|https://|http://|news:|file://|git://|ssh:|ftp://)[^\u{0000}-\u{001F}\u{007F}-\u{009F}<>"\s{-}\^⟨⟩`]+"#;
 // Optional suffix matches MSBuild diagnostic suffixes for path parsing in PathLikeWithPosition
 // https://learn.microsoft.com/en-us/visualstudio/msbuild/msbuild-diagnostic-format-for-tasks
 const WORD_REGEX: &str =
     r#"[\$\+\w.\[\]:/\\@\-~()]+(?:\((?:\d+|\d+,\d+)\))|[\$\+\w.\[\]:/\\@\-~()]+"#;
 
+/// What the terminal is connected to.
+#[derive(Default)]
+pub enum PtyBackend {
+    /// A PTY with the shell or the task's process running in it.
+    #[default]
+    Real,
+    /// No process at all: the terminal input is recorded instead, for the tests to assert on.
+    Fake(FakePty),
+}
+
+/// A PTY stand-in, which records all bytes written into it.
+#[derive(Clone, Default)]
+pub struct FakePty {
+    written: Arc<Mutex<Cursor<Vec<u8>>>>,
+}
+
+impl FakePty {
+    /// All bytes written into the PTY so far.
+    pub fn written(&self) -> Vec<u8> {
+        self.written.lock().unwrap().get_ref().clone()
+    }
+}
+
+enum PtySender {
+    Real(Notifier),
+    Fake(FakePty),
+}
+
+impl PtySender {
+    fn notify<B: Into<Cow<'static, [u8]>>>(&self, bytes: B) {
+        match self {
+            PtySender::Real(notifier) => notifier.notify(bytes),
+            PtySender::Fake(fake_pty) => {
+                fake_pty
+                    .written
+                    .lock()
+                    .unwrap()
+                    .write_all(&bytes.into())
+                    .ok();
+            }
+        }
+    }
+
+    fn send(&self, msg: Msg) {
+        if let PtySender::Real(notifier) = self {
+            notifier.0.send(msg).ok();
+        }
+    }
+}
+
 pub struct TerminalBuilder {
     terminal: Terminal,
     events_rx: UnboundedReceiver<AlacTermEvent>,
@@ -329,12 +460,13 @@ impl TerminalBuilder {
         mut env: HashMap<String, String>,
         cursor_shape: CursorShape,
         alternate_scroll: AlternateScroll,
-        max_scroll_history_lines: Option<usize>,
+        max_scroll_history_lines: usize,
         is_ssh_terminal: bool,
         window: AnyWindowHandle,
         completion_tx: Sender<()>,
+        pty_backend: PtyBackend,
         cx: &AppContext,
-    ) -> Result<TerminalBuilder> {
+    ) -> Result<TerminalBuilder, TerminalError> {
         // If the parent environment doesn't have a locale set
         // (As is the case when launched from a .app on MacOS),
         // and the Project doesn't have a locale set, then
@@ -367,6 +499,15 @@ impl TerminalBuilder {
                     terminal_title_override = title_override;
                     Some(alacritty_terminal::tty::Shell::new(program, args))
                 }
+                Shell::Login { program } => {
+                    let program = program
+                        .or_else(|| std::env::var("SHELL").ok())
+                        .unwrap_or_else(|| "/bin/sh".to_string());
+                    Some(alacritty_terminal::tty::Shell::new(
+                        program,
+                        vec!["-l".to_string()],
+                    ))
+                }
             };
 
             alacritty_terminal::tty::Options {
@@ -383,16 +524,11 @@ impl TerminalBuilder {
         alacritty_terminal::tty::setup_env();
 
         let default_cursor_style = AlacCursorStyle::from(cursor_shape);
-        let scrolling_history = if task.is_some() {
-            // Tasks like `cargo build --all` may produce a lot of output, ergo allow maximum scrolling.
-            // After the task finishes, we do not allow appending to that terminal, so small tasks output should not
-            // cause excessive memory usage over time.
-            MAX_SCROLL_HISTORY_LINES
-        } else {
-            max_scroll_history_lines
-                .unwrap_or(DEFAULT_SCROLL_HISTORY_LINES)
-                .min(MAX_SCROLL_HISTORY_LINES)
-        };
+        let scrolling_history = match &task {
+            Some(task) => task.max_output_lines.unwrap_or(max_scroll_history_lines),
+            None => max_scroll_history_lines,
+        }
+        .min(MAX_SCROLL_HISTORY_LINES);
         let config = Config {
             scrolling_history,
             default_cursor_style,
@@ -416,44 +552,97 @@ impl TerminalBuilder {
 
         let term = Arc::new(FairMutex::new(term));
 
-        //Setup the pty...
-        let pty = match tty::new(
-            &pty_options,
-            TerminalSize::default().into(),
-            window.window_id().as_u64(),
-        ) {
-            Ok(pty) => pty,
-            Err(error) => {
-                bail!(TerminalError {
-                    directory: working_directory,
-                    shell,
+        let (pty_tx, pty_info, output_limit, reported_working_directory) = match pty_backend {
+            PtyBackend::Real => {
+                if let Some(directory) = &working_directory {
+                    if !directory.is_dir() {
+                        return Err(TerminalError::InvalidWorkingDirectory(directory.clone()));
+                    }
+                }
+
+                //Setup the pty...
+                let pty = match tty::new(
+                    &pty_options,
+                    TerminalSize::default().into(),
+                    window.window_id().as_u64(),
+                ) {
+                    Ok(pty) => pty,
+                    Err(error) => {
+                        return Err(match shell_program(&shell) {
+                            Some(program) if error.kind() == std::io::ErrorKind::NotFound => {
+                                TerminalError::ShellNotFound(PathBuf::from(program))
+                            }
+                            _ => TerminalError::PtyCreationFailed {
+                                directory: working_directory,
+                                shell,
+                                source: error,
+                            },
+                        });
+                    }
+                };
+
+                let pty_info = PtyProcessInfo::new(&pty);
+                let output_limit = task
+                    .as_ref()
+                    .and_then(|task| task.max_output_lines)
+                    .map(|max_output_lines| Arc::new(OutputLimit::new(max_output_lines)));
+                // The working directory on the SSH host cannot be read from the local process.
+                let reported_working_directory =
+                    is_ssh_terminal.then(|| Arc::new(Mutex::new(None)));
+                let pty = ProcessedPty::new(pty, ZedListener(events_tx.clone()))
+                    .with_output_limit(output_limit.clone())
+                    .with_reported_working_directory(reported_working_directory.clone());
+
+                //And connect them together
+                let event_loop = EventLoop::new(
+                    term.clone(),
+                    ZedListener(events_tx.clone()),
+                    pty,
+                    pty_options.hold,
+                    false,
+                )
+                .map_err(|error| TerminalError::PtyCreationFailed {
+                    directory: working_directory.clone(),
+                    shell: shell.clone(),
                     source: error,
-                });
+                })?;
+
+                //Kick things off
+                let pty_tx = event_loop.channel();
+                let _io_thread = event_loop.spawn(); // DANGER
+                (
+                    PtySender::Real(Notifier(pty_tx)),
+                    pty_info,
+                    output_limit,
+                    reported_working_directory,
+                )
             }
+            PtyBackend::Fake(fake_pty) => (
+                PtySender::Fake(fake_pty),
+                PtyProcessInfo::without_process(),
+                None,
+                None,
+            ),
         };
 
-        let pty_info = PtyProcessInfo::new(&pty);
-
-        //And connect them together
-        let event_loop = EventLoop::new(
-            term.clone(),
-            ZedListener(events_tx.clone()),
-            pty,
-            pty_options.hold,
-            false,
-        )?;
-
-        //Kick things off
-        let pty_tx = event_loop.channel();
-        let _io_thread = event_loop.spawn(); // DANGER
+        let link_regexes = TerminalSettings::get_global(cx)
+            .link_patterns
+            .iter()
+            .filter_map(|pattern| {
+                RegexSearch::new(pattern)
+                    .map_err(|e| anyhow!("Invalid terminal link pattern {pattern:?}: {e}"))
+                    .log_err()
+            })
+            .collect();
 
         let terminal = Terminal {
             task,
-            pty_tx: Notifier(pty_tx),
+            pty_tx,
             completion_tx,
             term,
             term_config: config,
             title_override: terminal_title_override,
+            display_name: None,
             events: VecDeque::with_capacity(10), //Should never get this high.
             last_content: Default::default(),
             last_mouse: None,
@@ -469,8 +658,14 @@ impl TerminalBuilder {
             hovered_word: false,
             url_regex: RegexSearch::new(URL_REGEX).unwrap(),
             word_regex: RegexSearch::new(WORD_REGEX).unwrap(),
+            link_regexes,
             vi_mode_enabled: false,
             is_ssh_terminal,
+            is_ssh_disconnect: false,
+            created_at: Instant::now(),
+            applied_settings: (cursor_shape, alternate_scroll),
+            output_limit,
+            reported_working_directory,
         };
 
         Ok(TerminalBuilder {
@@ -604,7 +799,7 @@ pub enum SelectionPhase {
 }
 
 pub struct Terminal {
-    pty_tx: Notifier,
+    pty_tx: PtySender,
     completion_tx: Sender<()>,
     term: Arc<FairMutex<Term<ZedListener>>>,
     term_config: Config,
@@ -619,6 +814,8 @@ pub struct Terminal {
     pub breadcrumb_text: String,
     pub pty_info: PtyProcessInfo,
     title_override: Option<SharedString>,
+    /// A user-given name, shown instead of the shell or task derived title.
+    display_name: Option<SharedString>,
     scroll_px: Pixels,
     next_link_id: usize,
     selection_phase: SelectionPhase,
@@ -626,9 +823,19 @@ pub struct Terminal {
     hovered_word: bool,
     url_regex: RegexSearch,
     word_regex: RegexSearch,
+    link_regexes: Vec<RegexSearch>,
     task: Option<TaskState>,
     vi_mode_enabled: bool,
     is_ssh_terminal: bool,
+    /// Whether the SSH process has exited because of a lost connection.
+    is_ssh_disconnect: bool,
+    created_at: Instant,
+    /// The cursor shape and the alternate scroll settings, the terminal was created or last updated with.
+    applied_settings: (CursorShape, AlternateScroll),
+    /// Keeps the task's output beyond its `max_output_lines` from the terminal.
+    output_limit: Option<Arc<OutputLimit>>,
+    /// The working directory the shell has last reported with OSC 7, tracked for the SSH terminals.
+    reported_working_directory: Option<Arc<Mutex<Option<PathBuf>>>>,
 }
 
 pub struct TaskState {
@@ -636,11 +843,55 @@ pub struct TaskState {
     pub full_label: String,
     pub label: String,
     pub command_label: String,
+    /// The working directory shown to the user, either [`SpawnInTerminal::cwd_label`] or the task's `cwd`.
+    pub cwd_label: Option<String>,
+    /// The directory the task runs in: its `cwd`, or the project directory it was spawned in.
+    pub cwd: Option<PathBuf>,
     pub status: TaskStatus,
     pub completion_rx: Receiver<()>,
     pub hide: HideStrategy,
     pub show_summary: bool,
     pub show_command: bool,
+    /// The maximum number of output lines kept for the task, if limited.
+    pub max_output_lines: Option<usize>,
+    /// Whether the task has produced more output than `max_output_lines` allows.
+    pub output_truncated: bool,
+    /// The group of related tasks this task belongs to, if any.
+    pub group_id: Option<String>,
+    /// The test runner the task runs, which output is parsed into test results.
+    pub test_framework: Option<TestFramework>,
+    /// The exit code the task has reported on finishing, if any.
+    pub exit_code: Option<i32>,
+    /// Whether to spawn the task again after it crashes, up to its `max_restarts` times.
+    pub restart_on_crash: bool,
+    /// How many times in a row the task has crashed, including the runs it was restarted from.
+    pub crash_count: usize,
+    /// When the task's process was spawned.
+    pub started_at: Instant,
+    /// The task the terminal was spawned for, to spawn it again.
+    pub spawned_task: SpawnInTerminal,
+}
+
+impl TaskState {
+    /// Changes the task's status, unless the change is not a legal transition of the task's lifecycle.
+    pub fn set_status(&mut self, new: TaskStatus) -> Result<()> {
+        anyhow::ensure!(
+            self.status.can_transition_to(new),
+            "Task {:?} cannot change its status from {:?} to {:?}",
+            self.full_label,
+            self.status,
+            new
+        );
+        self.status = new;
+        Ok(())
+    }
+
+    /// Whether the task has crashed and should be spawned again, after [`TASK_RESTART_DELAY`].
+    pub fn should_restart(&self) -> bool {
+        matches!(self.status, TaskStatus::Crashed { .. })
+            && self.restart_on_crash
+            && self.crash_count < self.spawned_task.max_restarts
+    }
 }
 
 /// A status of the current terminal tab's task.
@@ -653,19 +904,59 @@ pub enum TaskStatus {
     Running,
     /// After the start, the task stopped running and reported its error code back.
     Completed { success: bool },
+    /// The task's process got killed by the given signal, other than the ones requesting it to stop.
+    Crashed { signal: i32 },
 }
 
 impl TaskStatus {
-    fn register_terminal_exit(&mut self) {
-        if self == &Self::Running {
-            *self = Self::Unknown;
+    /// The status of a task, which process has exited with the given code.
+    fn from_exit_code(error_code: i32) -> Self {
+        match crash_signal(error_code) {
+            Some(signal) => TaskStatus::Crashed { signal },
+            None => TaskStatus::Completed {
+                success: error_code == 0,
+            },
         }
     }
 
-    fn register_task_exit(&mut self, error_code: i32) {
-        *self = TaskStatus::Completed {
-            success: error_code == 0,
-        };
+    /// Only a running task may finish, and a finished task stays finished:
+    /// a restarted task gets a new [`TaskState`] instead.
+    fn can_transition_to(&self, new: TaskStatus) -> bool {
+        *self == TaskStatus::Running && new != TaskStatus::Running
+    }
+}
+
+/// How long to wait before spawning a crashed task again.
+pub const TASK_RESTART_DELAY: Duration = Duration::from_secs(2);
+
+/// The exit code of `ssh` on connection errors, including a dropped connection.
+/// The other exit codes are the ones of the remote shell, e.g. after the user has typed `exit`.
+pub const SSH_CONNECTION_LOST_EXIT_CODE: i32 = 255;
+
+/// The longest delay between two attempts to reconnect a disconnected SSH terminal.
+pub const SSH_RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// How many attempts in a row to reconnect a disconnected SSH terminal are made, before giving up.
+pub const SSH_RECONNECT_MAX_ATTEMPTS: usize = 10;
+
+/// How long to wait before the given attempt (starting from 1) to reconnect a disconnected SSH terminal:
+/// the delay doubles with every attempt, from a second up to [`SSH_RECONNECT_MAX_DELAY`].
+pub fn ssh_reconnect_delay(attempt: usize) -> Duration {
+    let exponent = attempt.saturating_sub(1).min(u32::BITS as usize - 1) as u32;
+    Duration::from_secs(1u64 << exponent).min(SSH_RECONNECT_MAX_DELAY)
+}
+
+/// The signal that has killed the task, deduced from its exit code: tasks are spawned in a shell,
+/// which reports the commands killed by a signal with the `128 + signal` exit code.
+/// Hangups, interrupts (Ctrl+C), kills (task cancellation) and terminations stop the task on request, so they are not crashes.
+fn crash_signal(error_code: i32) -> Option<i32> {
+    if cfg!(windows) {
+        return None;
+    }
+    match error_code.checked_sub(128)? {
+        1 | 2 | 9 | 15 => None,
+        signal @ 1..=64 => Some(signal),
+        _ => None,
     }
 }
 
@@ -709,6 +1000,7 @@ impl Terminal {
                 //NOOP, Handled in render
             }
             AlacTermEvent::Wakeup => {
+                self.check_output_limit();
                 cx.emit(Event::Wakeup);
 
                 if self.pty_info.has_changed() {
@@ -753,7 +1045,7 @@ impl Terminal {
 
                 self.last_content.size = new_size;
 
-                self.pty_tx.0.send(Msg::Resize(new_size.into())).ok();
+                self.pty_tx.send(Msg::Resize(new_size.into()));
 
                 term.resize(new_size);
             }
@@ -789,6 +1081,22 @@ impl Terminal {
 
                 cx.emit(Event::Wakeup);
             }
+            InternalEvent::ClearScrollback => {
+                // Move the visible lines into the history first, so both are dropped together.
+                term.clear_screen(ClearMode::All);
+                term.clear_screen(ClearMode::Saved);
+                term.grid_mut().cursor.point = AlacPoint::new(Line(0), Column(0));
+
+                // A task's output starts anew, so does the tracking of its limit.
+                if let Some(task) = self.task.as_mut() {
+                    task.output_truncated = false;
+                }
+                if let Some(output_limit) = &self.output_limit {
+                    output_limit.reset();
+                }
+
+                cx.emit(Event::Wakeup);
+            }
             InternalEvent::Scroll(scroll) => {
                 term.scroll_display(*scroll);
                 self.refresh_hovered_word();
@@ -923,6 +1231,13 @@ impl Terminal {
                 } else if let Some(url_match) = regex_match_at(term, point, &mut self.url_regex) {
                     let url = term.bounds_to_string(*url_match.start(), *url_match.end());
                     Some((url, true, url_match))
+                } else if let Some(link_match) = self
+                    .link_regexes
+                    .iter_mut()
+                    .find_map(|regex| regex_match_at(term, point, regex))
+                {
+                    let path = term.bounds_to_string(*link_match.start(), *link_match.end());
+                    Some((path, false, link_match))
                 } else if let Some(word_match) = regex_match_at(term, point, &mut self.word_regex) {
                     let file_path = term.bounds_to_string(*word_match.start(), *word_match.end());
 
@@ -1032,6 +1347,43 @@ impl Terminal {
         self.term.lock().set_options(self.term_config.clone());
     }
 
+    /// Applies the changes of the settings that can be changed in a running terminal:
+    /// the cursor shape, the alternate scroll mode and, outside of tasks, the scrollback size.
+    /// Whatever the terminal was spawned with (the shell, the working directory) stays as is.
+    pub fn update_settings(&mut self, settings: &TerminalSettings) {
+        let cursor_shape = settings.cursor_shape_for(self.task.is_some());
+        let alternate_scroll = settings.alternate_scroll_for(self.task.is_some());
+        let (old_cursor_shape, old_alternate_scroll) = self.applied_settings;
+        self.applied_settings = (cursor_shape, alternate_scroll);
+
+        let mut options_changed = false;
+        if cursor_shape != old_cursor_shape {
+            self.term_config.default_cursor_style = cursor_shape.into();
+            options_changed = true;
+        }
+        if self.task.is_none() {
+            let scrolling_history = settings
+                .scroll_history_lines()
+                .min(MAX_SCROLL_HISTORY_LINES);
+            if scrolling_history != self.term_config.scrolling_history {
+                self.term_config.scrolling_history = scrolling_history;
+                options_changed = true;
+            }
+        }
+
+        let mut term = self.term.lock();
+        if options_changed {
+            term.set_options(self.term_config.clone());
+        }
+        if alternate_scroll != old_alternate_scroll {
+            let mode = PrivateMode::Named(NamedPrivateMode::AlternateScroll);
+            match alternate_scroll {
+                AlternateScroll::On => term.set_private_mode(mode),
+                AlternateScroll::Off => term.unset_private_mode(mode),
+            }
+        }
+    }
+
     pub fn total_lines(&self) -> usize {
         let term = self.term.clone();
         let terminal = term.lock_unfair();
@@ -1093,6 +1445,11 @@ impl Terminal {
         self.events.push_back(InternalEvent::Clear)
     }
 
+    /// Clears both the screen and the whole scrollback, releasing the memory it used.
+    pub fn clear_scrollback(&mut self) {
+        self.events.push_back(InternalEvent::ClearScrollback)
+    }
+
     pub fn scroll_line_up(&mut self) {
         self.events
             .push_back(InternalEvent::Scroll(AlacScroll::Delta(1)));
@@ -1361,6 +1718,57 @@ impl Terminal {
         lines
     }
 
+    /// The whole text of the terminal, including its scrollback, without the trailing whitespace.
+    pub fn output_text(&self) -> String {
+        self.output_lines().join("\n")
+    }
+
+    /// The text of the rows currently visible in the terminal, one line per row,
+    /// without the trailing whitespace.
+    pub fn visible_content(&self) -> String {
+        let term = self.term.clone();
+        let terminal = term.lock_unfair();
+        visible_lines(&terminal).join("\n")
+    }
+
+    /// Writes the whole text of the terminal, including its scrollback, into the file at the given path.
+    pub fn export_scrollback(&self, path: &Path, cx: &AppContext) -> Task<Result<()>> {
+        let lines = self.output_lines();
+        let path = path.to_path_buf();
+        cx.background_executor().spawn(async move {
+            let file = std::fs::File::create(&path)
+                .with_context(|| format!("creating scrollback file {path:?}"))?;
+            let mut writer = BufWriter::new(file);
+            for line in lines {
+                writeln!(writer, "{line}")?;
+            }
+            writer.flush()?;
+            Ok(())
+        })
+    }
+
+    /// The grid cells store the printed characters only, so the lines contain no escape sequences.
+    fn output_lines(&self) -> Vec<String> {
+        let term = self.term.clone();
+        let terminal = term.lock_unfair();
+
+        let mut lines = Vec::new();
+        let mut current_line = terminal.topmost_line();
+        let bottommost_line = terminal.bottommost_line();
+        while current_line <= bottommost_line {
+            let mut line_buffer = String::new();
+            for cell in &terminal.grid()[current_line] {
+                line_buffer.push(cell.c);
+            }
+            lines.push(line_buffer.trim_end().to_string());
+            current_line = Line(current_line.0 + 1);
+        }
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+        lines
+    }
+
     pub fn focus_in(&self) {
         if self.last_content.mode.contains(TermMode::FOCUS_IN_OUT) {
             self.write_to_pty("\x1b[I".to_string());
@@ -1666,12 +2074,22 @@ impl Terminal {
 
     pub fn title(&self, truncate: bool) -> String {
         const MAX_CHARS: usize = 25;
+        if let Some(display_name) = &self.display_name {
+            return if truncate {
+                truncate_and_trailoff(display_name, MAX_CHARS)
+            } else {
+                display_name.to_string()
+            };
+        }
         match &self.task {
             Some(task_state) => {
                 if truncate {
                     truncate_and_trailoff(&task_state.label, MAX_CHARS)
                 } else {
-                    task_state.full_label.clone()
+                    match &task_state.cwd_label {
+                        Some(cwd_label) => format!("{} — {cwd_label}", task_state.full_label),
+                        None => task_state.full_label.clone(),
+                    }
                 }
             }
             None => self
@@ -1714,14 +2132,150 @@ impl Terminal {
         }
     }
 
+    pub fn set_display_name(&mut self, display_name: Option<SharedString>) {
+        self.display_name = display_name;
+    }
+
     pub fn can_navigate_to_selected_word(&self) -> bool {
         self.secondary_pressed && self.hovered_word
     }
 
+    pub fn is_ssh_terminal(&self) -> bool {
+        self.is_ssh_terminal
+    }
+
+    pub fn is_ssh_disconnect(&self) -> bool {
+        self.is_ssh_disconnect
+    }
+
+    /// The working directory on the SSH host, as last reported by the shell with OSC 7.
+    pub fn reported_working_directory(&self) -> Option<PathBuf> {
+        self.reported_working_directory
+            .as_ref()?
+            .lock()
+            .ok()?
+            .clone()
+    }
+
+    /// Carries the working directory over from the disconnected SSH terminal, this terminal reconnects,
+    /// until its shell reports one itself.
+    pub fn set_reported_working_directory(&mut self, working_directory: PathBuf) {
+        if let Some(reported_working_directory) = &self.reported_working_directory {
+            if let Ok(mut reported_working_directory) = reported_working_directory.lock() {
+                reported_working_directory.get_or_insert(working_directory);
+            }
+        }
+    }
+
+    /// When the terminal was created.
+    pub fn created_at(&self) -> Instant {
+        self.created_at
+    }
+
+    /// Whether the terminal's title was set by the shell with an OSC 0 or OSC 2 escape sequence.
+    pub fn has_shell_title(&self) -> bool {
+        !self.breadcrumb_text.is_empty()
+    }
+
+    /// Reads the name of the process in the foreground of the terminal from the system,
+    /// bypassing the process info cached since the terminal's last output.
+    pub fn current_process_name(&self, cx: &AppContext) -> Task<Option<String>> {
+        let Some(pid) = self.pty_info.pid() else {
+            return Task::ready(None);
+        };
+        cx.background_executor()
+            .spawn(async move { foreground_process_name(pid.as_u32()) })
+    }
+
+    /// Reloads the process info, if the process in the foreground has changed to the one with the given name.
+    pub fn refresh_process_name(
+        &mut self,
+        process_name: Option<String>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let Some(process_name) = process_name else {
+            return;
+        };
+        let cached_name = self
+            .pty_info
+            .current
+            .as_ref()
+            .map(|info| info.name.as_str());
+        if cached_name != Some(process_name.as_str()) && self.pty_info.has_changed() {
+            cx.emit(Event::TitleChanged);
+        }
+    }
+
+    /// Sends the signal (e.g. `SIGTSTP` or `SIGUSR1`) to the process group in the foreground of the terminal.
+    /// Fails with [`UnsupportedOnPlatform`] on the platforms without POSIX signals.
+    pub fn send_signal(&self, signal: i32) -> Result<()> {
+        #[cfg(unix)]
+        {
+            anyhow::ensure!(
+                !self.is_ssh_terminal,
+                "Cannot send signals to the processes on the remote host"
+            );
+            let pgid = self
+                .pty_info
+                .pid()
+                .context("The terminal has no process to send the signal to")?;
+            // SAFETY: `killpg` has no memory safety requirements, the errors are reported via `errno`.
+            if unsafe { libc::killpg(pgid.as_u32() as libc::pid_t, signal) } == -1 {
+                return Err(std::io::Error::last_os_error())
+                    .with_context(|| format!("Sending signal {signal} to process group {pgid}"));
+            }
+            Ok(())
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = signal;
+            Err(UnsupportedOnPlatform.into())
+        }
+    }
+
     pub fn task(&self) -> Option<&TaskState> {
         self.task.as_ref()
     }
 
+    /// Whether the terminal runs a task, rather than a plain shell.
+    pub fn is_task_terminal(&self) -> bool {
+        self.task.is_some()
+    }
+
+    /// The short label of the task the terminal runs, if it runs one.
+    pub fn task_label(&self) -> Option<&str> {
+        self.task.as_ref().map(|task| task.label.as_str())
+    }
+
+    /// Carries the crash count over from the crashed run of the task, this terminal restarts.
+    pub fn set_task_crash_count(&mut self, crash_count: usize) {
+        if let Some(task) = self.task.as_mut() {
+            task.crash_count = crash_count;
+        }
+    }
+
+    /// Marks the task output as truncated, once the task has printed more lines than its output line limit.
+    /// The task itself keeps running, its further output only goes into the full output file.
+    fn check_output_limit(&mut self) {
+        if let (Some(task), Some(output_limit)) = (self.task.as_mut(), &self.output_limit) {
+            task.output_truncated = output_limit.is_truncated();
+        }
+    }
+
+    /// Lifts the task output line limit, if any, allowing the terminal to keep the maximum scrollback.
+    /// Returns the file with the whole output of the task, including the lines the terminal has not shown.
+    pub fn show_full_output(&mut self) -> Option<PathBuf> {
+        let task = self.task.as_mut()?;
+        if task.max_output_lines.take().is_some() {
+            task.output_truncated = false;
+            self.term_config.scrolling_history = MAX_SCROLL_HISTORY_LINES;
+            self.term.lock().set_options(self.term_config.clone());
+        }
+        let output_limit = self.output_limit.as_ref()?;
+        output_limit.lift();
+        Some(output_limit.full_output_path().to_path_buf())
+    }
+
     pub fn wait_for_completed_task(&self, cx: &AppContext) -> Task<()> {
         if let Some(task) = self.task() {
             if task.status == TaskStatus::Running {
@@ -1743,7 +2297,10 @@ impl Terminal {
         let task = match &mut self.task {
             Some(task) => task,
             None => {
-                if error_code.is_none() {
+                if self.is_ssh_terminal && error_code == Some(SSH_CONNECTION_LOST_EXIT_CODE) {
+                    self.is_ssh_disconnect = true;
+                    cx.emit(Event::SshDisconnected);
+                } else if error_code.is_none() {
                     cx.emit(Event::CloseTerminal);
                 }
                 return;
@@ -1752,14 +2309,17 @@ impl Terminal {
         if task.status != TaskStatus::Running {
             return;
         }
-        match error_code {
-            Some(error_code) => {
-                task.status.register_task_exit(error_code);
-            }
-            None => {
-                task.status.register_terminal_exit();
-            }
+        task.exit_code = error_code;
+        let new_status = match error_code {
+            Some(error_code) => TaskStatus::from_exit_code(error_code),
+            None => TaskStatus::Unknown,
         };
+        // A failure here means that the task's lifecycle is tracked wrong somewhere.
+        task.set_status(new_status).warn_on_err();
+        if let TaskStatus::Crashed { .. } = task.status {
+            task.crash_count += 1;
+        }
+        let restarting = task.should_restart();
 
         let (finished_successfully, task_line, command_line) = task_summary(task, error_code);
         let mut lines_to_show = Vec::new();
@@ -1769,6 +2329,33 @@ impl Terminal {
         if task.show_command {
             lines_to_show.push(command_line.as_str());
         }
+        let truncation_line =
+            task.max_output_lines
+                .filter(|_| task.output_truncated)
+                .map(|max_output_lines| {
+                    format!("{TASK_DELIMITER}Output truncated at {max_output_lines} lines")
+                });
+        if let Some(truncation_line) = &truncation_line {
+            lines_to_show.push(truncation_line.as_str());
+        }
+        let restart_line = (task.restart_on_crash
+            && matches!(task.status, TaskStatus::Crashed { .. }))
+        .then(|| {
+            if restarting {
+                format!(
+                    "{TASK_DELIMITER}Restarting in {} seconds",
+                    TASK_RESTART_DELAY.as_secs()
+                )
+            } else {
+                format!(
+                    "{TASK_DELIMITER}Process crashed {} times; giving up",
+                    task.crash_count
+                )
+            }
+        });
+        if let Some(restart_line) = &restart_line {
+            lines_to_show.push(restart_line.as_str());
+        }
 
         if !lines_to_show.is_empty() {
             // SAFETY: the invocation happens on non `TaskStatus::Running` tasks, once,
@@ -1778,7 +2365,23 @@ impl Terminal {
             unsafe { append_text_to_term(&mut self.term.lock(), &lines_to_show) };
         }
 
-        match task.hide {
+        let test_framework = task.test_framework;
+        let hide = task.hide;
+        if let Some(test_framework) = test_framework {
+            let mut parser = test_output::parser_for(test_framework);
+            for line in self.output_lines() {
+                if let Some(result) = parser.parse_line(&line) {
+                    cx.emit(Event::TestResult(result));
+                }
+            }
+        }
+
+        cx.emit(Event::TaskFinished);
+        // Keep the terminal around, to replace it with the restarted task.
+        if restarting {
+            return;
+        }
+        match hide {
             HideStrategy::Never => {}
             HideStrategy::Always => {
                 cx.emit(Event::CloseTerminal);
@@ -1795,22 +2398,44 @@ impl Terminal {
 const TASK_DELIMITER: &str = "⏵ ";
 fn task_summary(task: &TaskState, error_code: Option<i32>) -> (bool, String, String) {
     let escaped_full_label = task.full_label.replace("\r\n", "\r").replace('\n', "\r");
-    let (success, task_line) = match error_code {
-        Some(0) => {
-            (true, format!("{TASK_DELIMITER}Task `{escaped_full_label}` finished successfully"))
+    let duration = format_task_duration(task.started_at.elapsed());
+    let (success, task_line) = match (task.status, error_code) {
+        (TaskStatus::Crashed { signal }, _) => {
+            (false, format!("{TASK_DELIMITER}Task `{escaped_full_label}` crashed with signal {signal} after {duration}"))
+        }
+        (_, Some(0)) => {
+            (true, format!("{TASK_DELIMITER}Task `{escaped_full_label}` finished successfully in {duration}"))
         }
-        Some(error_code) => {
-            (false, format!("{TASK_DELIMITER}Task `{escaped_full_label}` finished with non-zero error code: {error_code}"))
+        (_, Some(error_code)) => {
+            (false, format!("{TASK_DELIMITER}Task `{escaped_full_label}` finished with non-zero error code: {error_code} in {duration}"))
         }
-        None => {
-            (false, format!("{TASK_DELIMITER}Task `{escaped_full_label}` finished"))
+        (_, None) => {
+            (false, format!("{TASK_DELIMITER}Task `{escaped_full_label}` finished in {duration}"))
         }
     };
     let escaped_command_label = task.command_label.replace("\r\n", "\r").replace('\n', "\r");
-    let command_line = format!("{TASK_DELIMITER}Command: {escaped_command_label}");
+    let command_line = match &task.cwd_label {
+        Some(cwd_label) => {
+            let escaped_cwd_label = cwd_label.replace("\r\n", "\r").replace('\n', "\r");
+            format!("{TASK_DELIMITER}Command: {escaped_command_label} (in {escaped_cwd_label})")
+        }
+        None => format!("{TASK_DELIMITER}Command: {escaped_command_label}"),
+    };
     (success, task_line, command_line)
 }
 
+fn format_task_duration(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    if millis < 1000 {
+        format!("{millis}ms")
+    } else if millis < 60_000 {
+        format!("{:.1}s", duration.as_secs_f64())
+    } else {
+        let secs = duration.as_secs();
+        format!("{}m {}s", secs / 60, secs % 60)
+    }
+}
+
 /// Appends a stringified task summary to the terminal, after its output.
 ///
 /// SAFETY: This function should only be called after terminal's PTY is no longer alive.
@@ -1848,9 +2473,57 @@ unsafe fn append_text_to_term(term: &mut Term<ZedListener>, text_lines: &[&str])
     }
 }
 
+#[cfg(target_os = "linux")]
+fn foreground_process_name(pid: u32) -> Option<String> {
+    let name = std::fs::read_to_string(format!("/proc/{pid}/comm")).ok()?;
+    Some(name.trim_end().to_string()).filter(|name| !name.is_empty())
+}
+
+#[cfg(target_os = "macos")]
+fn foreground_process_name(pid: u32) -> Option<String> {
+    let output = std::process::Command::new("ps")
+        .args(["-o", "comm=", "-p", &pid.to_string()])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    // `ps` prints the path of the executable.
+    let command = String::from_utf8(output.stdout).ok()?;
+    let name = Path::new(command.trim()).file_name()?.to_str()?;
+    Some(name.to_string())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn foreground_process_name(_pid: u32) -> Option<String> {
+    None
+}
+
+/// The grid rows shown at the current scroll position, with the wide characters' spacer cells skipped.
+fn visible_lines<T>(term: &Term<T>) -> Vec<String> {
+    let display_offset = term.grid().display_offset() as i32;
+    (0..term.screen_lines() as i32)
+        .map(|row| {
+            let mut line = String::new();
+            for cell in &term.grid()[Line(row - display_offset)] {
+                if !cell.flags.contains(Flags::WIDE_CHAR_SPACER) {
+                    line.push(cell.c);
+                }
+            }
+            line.trim_end().to_string()
+        })
+        .collect()
+}
+
+impl Display for Terminal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.visible_content())
+    }
+}
+
 impl Drop for Terminal {
     fn drop(&mut self) {
-        self.pty_tx.0.send(Msg::Shutdown).ok();
+        self.pty_tx.send(Msg::Shutdown);
     }
 }
 
@@ -1990,14 +2663,25 @@ pub fn rgba_color(r: u8, g: u8, b: u8) -> Hsla {
 #[cfg(test)]
 mod tests {
     use alacritty_terminal::{
+        event::VoidListener,
+        grid::Scroll as AlacScroll,
         index::{Column, Line, Point as AlacPoint},
-        term::cell::Cell,
+        term::{cell::Cell, Config},
+        vte::ansi::Handler,
+        Term,
     };
-    use gpui::{point, size, Pixels};
+    use collections::HashMap;
+    use gpui::{point, size, Pixels, SemanticVersion, TestAppContext};
     use rand::{distributions::Alphanumeric, rngs::ThreadRng, thread_rng, Rng};
+    use settings::SettingsStore;
+    use std::time::Instant;
+    use task::{Shell, TaskContext, TaskTemplate};
 
     use crate::{
-        content_index_for_mouse, rgb_for_index, IndexedCell, TerminalContent, TerminalSize,
+        content_index_for_mouse, rgb_for_index,
+        terminal_settings::{AlternateScroll, CursorShape},
+        visible_lines, FakePty, IndexedCell, PtyBackend, TaskState, TaskStatus, TerminalBuilder,
+        TerminalContent, TerminalSize,
     };
 
     #[test]
@@ -2175,4 +2859,188 @@ mod tests {
             vec!["Main.cs:20:5:Error", "desc"],
         );
     }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_crash_signal() {
+        // SIGSEGV and SIGABRT
+        assert_eq!(crate::crash_signal(139), Some(11));
+        assert_eq!(crate::crash_signal(134), Some(6));
+        // Ctrl+C and the task cancellation
+        assert_eq!(crate::crash_signal(130), None);
+        assert_eq!(crate::crash_signal(137), None);
+        // Plain failures, e.g. a Rust panic
+        assert_eq!(crate::crash_signal(1), None);
+        assert_eq!(crate::crash_signal(101), None);
+        assert_eq!(crate::crash_signal(255), None);
+    }
+
+    #[test]
+    fn test_task_status_transitions() {
+        let finished = [
+            TaskStatus::Unknown,
+            TaskStatus::Completed { success: true },
+            TaskStatus::Completed { success: false },
+            TaskStatus::Crashed { signal: 11 },
+        ];
+        for status in finished {
+            assert!(TaskStatus::Running.can_transition_to(status));
+            assert!(!status.can_transition_to(TaskStatus::Running));
+            for other in finished {
+                assert!(!status.can_transition_to(other));
+            }
+        }
+        assert!(!TaskStatus::Running.can_transition_to(TaskStatus::Running));
+    }
+
+    #[test]
+    fn test_should_restart_stops_after_max_restarts() {
+        let mut task = test_task_state(|task| {
+            task.restart_on_crash = true;
+            task.max_restarts = 2;
+        });
+        task.status = TaskStatus::Crashed { signal: 11 };
+        task.crash_count = 1;
+        assert!(task.should_restart());
+        task.crash_count = 2;
+        assert!(!task.should_restart());
+
+        let mut task = test_task_state(|task| task.max_restarts = 2);
+        task.status = TaskStatus::Crashed { signal: 11 };
+        task.crash_count = 1;
+        assert!(!task.should_restart());
+    }
+
+    #[test]
+    fn test_ssh_reconnect_delay() {
+        let delays = (1..=7)
+            .map(|attempt| crate::ssh_reconnect_delay(attempt).as_secs())
+            .collect::<Vec<_>>();
+        assert_eq!(delays, [1, 2, 4, 8, 16, 30, 30]);
+        assert_eq!(
+            crate::ssh_reconnect_delay(usize::MAX),
+            crate::SSH_RECONNECT_MAX_DELAY
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_foreground_process_name() {
+        let name = crate::foreground_process_name(std::process::id()).unwrap();
+        let executable = std::env::current_exe().unwrap();
+        // The kernel truncates the process names to 15 bytes.
+        assert!(executable
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with(&name));
+    }
+
+    #[test]
+    fn test_visible_lines() {
+        // 4 lines of 10 columns.
+        let terminal_size = TerminalSize::new(
+            Pixels::from(10.),
+            Pixels::from(5.),
+            size(Pixels::from(50.), Pixels::from(40.)),
+        );
+        let mut term = Term::new(Config::default(), &terminal_size, VoidListener);
+        for (index, line) in ["one", "two  ", "three", "four", "wide 字"]
+            .iter()
+            .enumerate()
+        {
+            if index > 0 {
+                term.carriage_return();
+                term.linefeed();
+            }
+            for c in line.chars() {
+                term.input(c);
+            }
+        }
+
+        assert_eq!(visible_lines(&term), ["two", "three", "four", "wide 字"]);
+
+        term.scroll_display(AlacScroll::Delta(1));
+        assert_eq!(visible_lines(&term), ["one", "two", "three", "four"]);
+    }
+
+    fn test_task_state(configure: impl FnOnce(&mut TaskTemplate)) -> TaskState {
+        let mut template = TaskTemplate {
+            label: "test task".to_string(),
+            command: "cargo".to_string(),
+            ..TaskTemplate::default()
+        };
+        configure(&mut template);
+        let spawned_task = template
+            .resolve_task("test", &TaskContext::default())
+            .unwrap()
+            .resolved
+            .unwrap();
+        TaskState {
+            id: spawned_task.id.clone(),
+            full_label: spawned_task.full_label.clone(),
+            label: spawned_task.label.clone(),
+            command_label: spawned_task.command_label.clone(),
+            cwd_label: None,
+            cwd: None,
+            status: TaskStatus::Running,
+            completion_rx: smol::channel::bounded(1).1,
+            hide: spawned_task.hide,
+            show_summary: spawned_task.show_summary,
+            show_command: spawned_task.show_command,
+            max_output_lines: spawned_task.max_output_lines,
+            output_truncated: false,
+            group_id: None,
+            test_framework: None,
+            exit_code: None,
+            restart_on_crash: spawned_task.restart_on_crash,
+            crash_count: 0,
+            started_at: Instant::now(),
+            spawned_task,
+        }
+    }
+
+    #[gpui::test]
+    fn test_task_scrolling_history(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            release_channel::init(SemanticVersion::default(), cx);
+            crate::init(cx);
+        });
+        let window = cx.add_empty_window().handle();
+        let scrolling_history = |task: Option<TaskState>, cx: &mut TestAppContext| {
+            cx.update(|cx| {
+                TerminalBuilder::new(
+                    None,
+                    task,
+                    Shell::System,
+                    HashMap::default(),
+                    CursorShape::default(),
+                    AlternateScroll::On,
+                    5_000,
+                    false,
+                    window,
+                    smol::channel::bounded(1).0,
+                    PtyBackend::Fake(FakePty::default()),
+                    cx,
+                )
+                .unwrap()
+                .terminal
+                .term_config
+                .scrolling_history
+            })
+        };
+
+        // The project passes the task's `override_max_scroll_history_lines` as the terminal's scrollback size.
+        assert_eq!(scrolling_history(None, cx), 5_000);
+        assert_eq!(scrolling_history(Some(test_task_state(|_| {})), cx), 5_000);
+        assert_eq!(
+            scrolling_history(
+                Some(test_task_state(|task| task.max_output_lines = Some(300))),
+                cx
+            ),
+            300
+        );
+    }
 }
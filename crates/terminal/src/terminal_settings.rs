@@ -3,12 +3,19 @@ use alacritty_terminal::vte::ansi::{
 };
 use collections::HashMap;
 use gpui::{
-    px, AbsoluteLength, AppContext, FontFallbacks, FontFeatures, FontWeight, Pixels, SharedString,
+    px, AbsoluteLength, AppContext, FontFallbacks, FontFeatures, FontStyle, FontWeight, Pixels,
+    SharedString,
 };
-use schemars::{gen::SchemaGenerator, schema::RootSchema, JsonSchema};
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{RootSchema, Schema, SchemaObject, StringValidation},
+    JsonSchema,
+};
+use serde::{de::Error as _, Deserialize as _, Deserializer};
 use serde_derive::{Deserialize, Serialize};
 use settings::{add_references_to_properties, SettingsJsonSchemaParams, SettingsSources};
-use std::path::PathBuf;
+use std::{path::PathBuf, sync::OnceLock, time::Duration};
+use sysinfo::{MemoryRefreshKind, RefreshKind, System};
 use task::Shell;
 
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
@@ -24,7 +31,7 @@ pub struct Toolbar {
     pub breadcrumbs: bool,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct TerminalSettings {
     pub shell: Shell,
     pub working_directory: WorkingDirectory,
@@ -33,11 +40,16 @@ pub struct TerminalSettings {
     pub font_fallbacks: Option<FontFallbacks>,
     pub font_features: Option<FontFeatures>,
     pub font_weight: Option<FontWeight>,
+    pub font_style: Option<FontStyle>,
+    pub ligatures: bool,
     pub line_height: TerminalLineHeight,
     pub env: HashMap<String, String>,
+    pub env_files: Vec<PathBuf>,
     pub cursor_shape: Option<CursorShape>,
+    pub task_cursor_shape: Option<CursorShape>,
     pub blinking: TerminalBlink,
     pub alternate_scroll: AlternateScroll,
+    pub task_alternate_scroll: Option<bool>,
     pub option_as_meta: bool,
     pub copy_on_select: bool,
     pub button: bool,
@@ -45,7 +57,15 @@ pub struct TerminalSettings {
     pub default_width: Pixels,
     pub default_height: Pixels,
     pub detect_venv: VenvSettings,
-    pub max_scroll_history_lines: Option<usize>,
+    pub max_scroll_history_lines: Option<ScrollHistoryLimit>,
+    pub task_output_history: usize,
+    pub remote_locale: String,
+    pub ssh_forward_env: Vec<String>,
+    pub link_patterns: Vec<String>,
+    pub process_name_poll_interval_ms: u64,
+    pub max_terminals: Option<usize>,
+    pub eviction_policy: TerminalEvictionPolicy,
+    pub preferred_panel_orientation: PanelOrientation,
     pub toolbar: Toolbar,
 }
 
@@ -60,12 +80,123 @@ pub enum VenvSettings {
         /// in your project's settings, rather than globally.
         activate_script: Option<ActivateScript>,
         directories: Option<Vec<PathBuf>>,
+        /// For how many seconds a found virtual environment is reused for new terminals,
+        /// before the directories are searched again. 0 searches them for every terminal.
+        ///
+        /// Default: 60
+        cache_ttl_secs: Option<u64>,
     },
 }
 
-pub struct VenvSettingsContent<'a> {
+#[derive(Clone, Debug)]
+pub struct VenvSettingsContent {
     pub activate_script: ActivateScript,
-    pub directories: &'a [PathBuf],
+    pub directories: Vec<PathBuf>,
+    pub cache_ttl: Duration,
+}
+
+impl TerminalSettings {
+    /// The cursor shape to use for a terminal, depending on whether it runs a task.
+    pub fn cursor_shape_for(&self, is_task: bool) -> CursorShape {
+        let task_cursor_shape = if is_task {
+            self.task_cursor_shape
+        } else {
+            None
+        };
+        task_cursor_shape.or(self.cursor_shape).unwrap_or_default()
+    }
+
+    /// The Alternate Scroll mode to start a terminal with, depending on whether it runs a task:
+    /// tasks do not run pagers, so their terminals scroll with the mouse wheel, unless `task_alternate_scroll` is set.
+    pub fn alternate_scroll_for(&self, is_task: bool) -> AlternateScroll {
+        if is_task && !self.task_alternate_scroll.unwrap_or(false) {
+            AlternateScroll::Off
+        } else {
+            self.alternate_scroll
+        }
+    }
+
+    /// The scrollback size for the terminals, with [`ScrollHistoryLimit::Auto`] resolved
+    /// against the memory available when it was first needed.
+    pub fn scroll_history_lines(&self) -> usize {
+        match self.max_scroll_history_lines {
+            None => crate::DEFAULT_SCROLL_HISTORY_LINES,
+            Some(ScrollHistoryLimit::Lines(lines)) => lines,
+            Some(ScrollHistoryLimit::Auto(_)) => auto_scroll_history_lines(
+                available_memory(),
+                self.max_terminals.unwrap_or(AUTO_SCROLL_HISTORY_TERMINALS),
+            ),
+        }
+    }
+}
+
+/// How many terminals share the memory budget of [`ScrollHistoryLimit::Auto`],
+/// when `max_terminals` does not limit their number.
+const AUTO_SCROLL_HISTORY_TERMINALS: usize = 10;
+/// The approximate memory taken by a scrollback line: a 120 columns wide row of 24 bytes cells.
+const SCROLL_HISTORY_LINE_BYTES: u64 = 120 * 24;
+
+/// Queries the available memory once: refreshing it on every terminal spawn is too slow.
+fn available_memory() -> u64 {
+    static AVAILABLE_MEMORY: OnceLock<u64> = OnceLock::new();
+    *AVAILABLE_MEMORY.get_or_init(|| {
+        System::new_with_specifics(
+            RefreshKind::new().with_memory(MemoryRefreshKind::new().with_ram()),
+        )
+        .available_memory()
+    })
+}
+
+fn auto_scroll_history_lines(available_memory: u64, terminals: usize) -> usize {
+    let budget = available_memory / 100 / terminals.max(1) as u64;
+    (budget / SCROLL_HISTORY_LINE_BYTES) as usize
+}
+
+/// Names that the shells accept for their variables.
+const ENV_NAME_PATTERN: &str = "^[A-Za-z_][A-Za-z0-9_]*$";
+
+fn is_valid_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Rejects the variable names that would make spawning the terminal fail, with an error shown on loading the settings.
+fn deserialize_env<'de, D>(deserializer: D) -> Result<Option<HashMap<String, String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let env = Option::<HashMap<String, String>>::deserialize(deserializer)?;
+    if let Some(name) = env
+        .iter()
+        .flat_map(|env| env.keys())
+        .find(|name| !is_valid_env_name(name))
+    {
+        return Err(D::Error::custom(format!(
+            "invalid terminal environment variable name {name:?}: \
+            names must start with a letter or `_`, followed by letters, digits or `_`"
+        )));
+    }
+    Ok(env)
+}
+
+fn env_schema(generator: &mut SchemaGenerator) -> Schema {
+    let mut schema = generator
+        .subschema_for::<Option<HashMap<String, String>>>()
+        .into_object();
+    schema.object().property_names = Some(Box::new(
+        SchemaObject {
+            string: Some(Box::new(StringValidation {
+                pattern: Some(ENV_NAME_PATTERN.to_string()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into(),
+    ));
+    schema.into()
 }
 
 impl VenvSettings {
@@ -75,9 +206,11 @@ impl VenvSettings {
             VenvSettings::On {
                 activate_script,
                 directories,
+                cache_ttl_secs,
             } => Some(VenvSettingsContent {
                 activate_script: activate_script.unwrap_or(ActivateScript::Default),
-                directories: directories.as_deref().unwrap_or(&[]),
+                directories: directories.clone().unwrap_or_default(),
+                cache_ttl: Duration::from_secs(cache_ttl_secs.unwrap_or(60)),
             }),
         }
     }
@@ -127,17 +260,42 @@ pub struct TerminalSettingsContent {
     pub line_height: Option<TerminalLineHeight>,
     pub font_features: Option<FontFeatures>,
     /// Sets the terminal's font weight in CSS weight units 0-900.
+    /// If this option is not included,
+    /// the terminal will default to matching the buffer's font weight.
     pub font_weight: Option<f32>,
+    /// Sets the terminal's font style: "normal", "italic" or "oblique".
+    /// If this option is not included,
+    /// the terminal will default to matching the buffer's font style.
+    pub font_style: Option<FontStyle>,
+    /// Whether to render the programming ligatures of the font (e.g. `->`, `=>`, `!=`)
+    /// as single glyphs, spanning the cells of their characters.
+    ///
+    /// Default: false
+    pub ligatures: Option<bool>,
     /// Any key-value pairs added to this list will be added to the terminal's
     /// environment. Use `:` to separate multiple values.
     ///
     /// Default: {}
+    #[serde(default, deserialize_with = "deserialize_env")]
+    #[schemars(schema_with = "env_schema")]
     pub env: Option<HashMap<String, String>>,
+    /// Paths to `.env` files (absolute or relative to the worktree root), whose variables
+    /// are added to the terminal's environment. Variables from `env` take precedence,
+    /// missing files are skipped. Only read in local projects, not over SSH.
+    ///
+    /// Default: []
+    pub env_files: Option<Vec<PathBuf>>,
     /// Default cursor shape for the terminal.
     /// Can be "bar", "block", "underline", or "hollow".
     ///
     /// Default: None
     pub cursor_shape: Option<CursorShape>,
+    /// Cursor shape for the terminals running tasks.
+    /// Can be "bar", "block", "underline", or "hollow".
+    /// If not set, `cursor_shape` is used.
+    ///
+    /// Default: None
+    pub task_cursor_shape: Option<CursorShape>,
     /// Sets the cursor blinking behavior in the terminal.
     ///
     /// Default: terminal_controlled
@@ -149,6 +307,12 @@ pub struct TerminalSettingsContent {
     ///
     /// Default: off
     pub alternate_scroll: Option<AlternateScroll>,
+    /// Whether the terminals running tasks use `alternate_scroll` too.
+    /// Otherwise, Alternate Scroll mode is off for them by default, so that the mouse wheel
+    /// scrolls the task output.
+    ///
+    /// Default: false
+    pub task_alternate_scroll: Option<bool>,
     /// Sets whether the option key behaves as the meta key.
     ///
     /// Default: false
@@ -179,12 +343,59 @@ pub struct TerminalSettingsContent {
     pub detect_venv: Option<VenvSettings>,
     /// The maximum number of lines to keep in the scrollback history.
     /// Maximum allowed value is 100_000, all values above that will be treated as 100_000.
-    /// 0 disables the scrolling.
+    /// 0 disables the scrolling. "auto" sizes the scrollback after the available memory,
+    /// so that the scrollbacks of all terminals take no more than 1% of it.
     /// Existing terminals will not pick up this change until they are recreated.
     /// See <a href="https://github.com/alacritty/alacritty/blob/cb3a79dbf6472740daca8440d5166c1d4af5029e/extra/man/alacritty.5.scd?plain=1#L207-L213">Alacritty documentation</a> for more information.
     ///
     /// Default: 10_000
-    pub max_scroll_history_lines: Option<usize>,
+    pub max_scroll_history_lines: Option<ScrollHistoryLimit>,
+    /// How many of the latest outputs to keep for each task, to compare the task's runs with each other.
+    /// 0 disables the comparison.
+    ///
+    /// Default: 3
+    pub task_output_history: Option<usize>,
+    /// The locale to set as `LC_ALL` in terminals running over ssh, when neither `LC_ALL`
+    /// nor `LANG` is set in the terminal's environment. An empty string disables this.
+    ///
+    /// Default: "C.UTF-8"
+    pub remote_locale: Option<String>,
+    /// The environment variables to pass to terminals running over ssh with `-o SendEnv`,
+    /// instead of exporting them in the remote command. The remote `sshd_config` has to
+    /// accept them with `AcceptEnv`, otherwise they are dropped.
+    ///
+    /// Default: []
+    pub ssh_forward_env: Option<Vec<String>>,
+    /// Additional regular expressions for the terminal output to open as paths on click,
+    /// checked before the default path detection. A match may end with a `:row:column` suffix.
+    ///
+    /// Default: []
+    pub link_patterns: Option<Vec<String>>,
+    /// How often to check the process in the foreground of a shell terminal, in milliseconds,
+    /// to show its name in the terminal's title as soon as it changes. 0 disables the checks,
+    /// the title is then only updated when the terminal prints something.
+    ///
+    /// Default: 2000
+    pub process_name_poll_interval_ms: Option<u64>,
+    /// The maximum number of terminals to keep open in a project, unlimited if not set.
+    ///
+    /// Default: None
+    pub max_terminals: Option<usize>,
+    /// What to do when a new terminal is opened, while `max_terminals` are open already:
+    /// * `oldest_completed` — close the oldest terminal, which task has finished (default)
+    /// * `oldest_any` — close the oldest terminal
+    /// * `error` — do not open the new terminal
+    ///
+    /// Default: oldest_completed
+    pub eviction_policy: Option<TerminalEvictionPolicy>,
+    /// Where to dock the terminal panel, when a task is spawned in it:
+    /// * `docked` — keep the panel where it is (default)
+    /// * `horizontal` — at the bottom, below the editors
+    /// * `vertical` — at the right, next to the editors
+    /// * `auto` — at the right if the editors are split side by side, at the bottom if they are stacked
+    ///
+    /// Default: docked
+    pub preferred_panel_orientation: Option<PanelOrientation>,
     /// Toolbar related settings
     pub toolbar: Option<ToolbarContent>,
 }
@@ -248,6 +459,23 @@ impl TerminalLineHeight {
     }
 }
 
+/// How many lines to keep in the scrollback history.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(untagged)]
+pub enum ScrollHistoryLimit {
+    /// A fixed number of lines.
+    Lines(usize),
+    /// As many lines as fit into 1% of the available memory, shared by all terminals.
+    Auto(AutoScrollHistoryLimit),
+}
+
+/// The `"auto"` value of [`ScrollHistoryLimit`].
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoScrollHistoryLimit {
+    Auto,
+}
+
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum TerminalBlink {
@@ -260,6 +488,34 @@ pub enum TerminalBlink {
     On,
 }
 
+/// Which terminal to close, when a new one is opened while `max_terminals` are open already.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TerminalEvictionPolicy {
+    /// The oldest terminal of a finished task. Nothing is closed, if there is no such terminal.
+    #[default]
+    OldestCompleted,
+    /// The oldest terminal, regardless of what runs in it.
+    OldestAny,
+    /// None, the new terminal is not opened instead.
+    Error,
+}
+
+/// Where to dock the terminal panel, when a task is spawned in it.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PanelOrientation {
+    /// Keep the panel docked where it is.
+    #[default]
+    Docked,
+    /// Dock the panel at the bottom, below the editors.
+    Horizontal,
+    /// Dock the panel at the right, next to the editors.
+    Vertical,
+    /// Follow the editors' split: at the right if they are side by side, at the bottom if stacked.
+    Auto,
+}
+
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum AlternateScroll {
@@ -329,3 +585,32 @@ impl From<CursorShape> for AlacCursorStyle {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{auto_scroll_history_lines, is_valid_env_name};
+
+    #[test]
+    fn test_env_name_validation() {
+        assert!(is_valid_env_name("PATH"));
+        assert!(is_valid_env_name("_private"));
+        assert!(is_valid_env_name("RUST_LOG2"));
+        assert!(!is_valid_env_name(""));
+        assert!(!is_valid_env_name("2FAST"));
+        assert!(!is_valid_env_name("WITH SPACE"));
+        assert!(!is_valid_env_name("KEY=VALUE"));
+        assert!(!is_valid_env_name("NUL\0"));
+    }
+
+    #[test]
+    fn test_auto_scroll_history_lines() {
+        let gigabyte = 1024 * 1024 * 1024;
+        assert_eq!(auto_scroll_history_lines(8 * gigabyte, 10), 2982);
+        assert_eq!(auto_scroll_history_lines(32 * gigabyte, 10), 11930);
+        assert_eq!(
+            auto_scroll_history_lines(32 * gigabyte, 0),
+            auto_scroll_history_lines(32 * gigabyte, 1)
+        );
+        assert_eq!(auto_scroll_history_lines(0, 10), 0);
+    }
+}
@@ -0,0 +1,277 @@
+//! Recognizes the per-test result lines that common test runners print,
+//! so that a task running the tests can report them in a structured form.
+
+use std::time::Duration;
+
+use task::TestFramework;
+
+/// How a single test has ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestOutcome {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+/// A result of a single test, as reported by the test runner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestResult {
+    pub name: String,
+    pub status: TestOutcome,
+    /// Zero, if the test runner does not report the test durations.
+    pub duration: Duration,
+    pub message: Option<String>,
+}
+
+/// Parses the output of a test runner, line by line, as it was printed into the terminal.
+pub trait TestOutputParser {
+    fn parse_line(&mut self, line: &str) -> Option<TestResult>;
+}
+
+pub fn parser_for(framework: TestFramework) -> Box<dyn TestOutputParser> {
+    match framework {
+        TestFramework::Cargo => Box::new(CargoTestParser),
+        TestFramework::Pytest => Box::new(PytestParser),
+        TestFramework::Jest => Box::new(JestParser),
+        TestFramework::Go => Box::new(GoTestParser),
+    }
+}
+
+/// `test tests::it_works ... ok`
+struct CargoTestParser;
+
+impl TestOutputParser for CargoTestParser {
+    fn parse_line(&mut self, line: &str) -> Option<TestResult> {
+        let (name, status) = line.strip_prefix("test ")?.split_once(" ... ")?;
+        let (status, message) = match status.split_once(", ") {
+            Some((status, message)) => (status, Some(message.to_string())),
+            None => (status, None),
+        };
+        let status = match status {
+            "ok" => TestOutcome::Passed,
+            "FAILED" => TestOutcome::Failed,
+            "ignored" => TestOutcome::Skipped,
+            _ => return None,
+        };
+        Some(TestResult {
+            name: name.trim().to_string(),
+            status,
+            duration: Duration::ZERO,
+            message,
+        })
+    }
+}
+
+/// `tests/test_math.py::test_add PASSED          [ 50%]`, printed by `pytest -v`.
+struct PytestParser;
+
+impl TestOutputParser for PytestParser {
+    fn parse_line(&mut self, line: &str) -> Option<TestResult> {
+        let mut words = line.split_whitespace();
+        let name = words.next().filter(|name| name.contains("::"))?;
+        let status = match words.next()? {
+            "PASSED" | "XPASS" => TestOutcome::Passed,
+            "FAILED" | "ERROR" => TestOutcome::Failed,
+            "SKIPPED" | "XFAIL" => TestOutcome::Skipped,
+            _ => return None,
+        };
+        Some(TestResult {
+            name: name.to_string(),
+            status,
+            duration: Duration::ZERO,
+            message: None,
+        })
+    }
+}
+
+/// `✓ adds numbers (3 ms)`, printed by jest, or `✔ adds numbers (3ms)`, printed by mocha.
+struct JestParser;
+
+impl TestOutputParser for JestParser {
+    fn parse_line(&mut self, line: &str) -> Option<TestResult> {
+        let line = line.trim();
+        let mut chars = line.chars();
+        let status = match chars.next()? {
+            '✓' | '✔' => TestOutcome::Passed,
+            '✕' | '✗' | '✖' => TestOutcome::Failed,
+            '○' => TestOutcome::Skipped,
+            _ => return None,
+        };
+        let rest = chars.as_str().trim();
+        let rest = rest.strip_prefix("skipped ").unwrap_or(rest);
+        let (name, duration) = match rest
+            .strip_suffix("ms)")
+            .and_then(|rest| rest.rsplit_once(" ("))
+            .and_then(|(name, millis)| Some((name, millis.trim().parse::<u64>().ok()?)))
+        {
+            Some((name, millis)) => (name, Duration::from_millis(millis)),
+            None => (rest, Duration::ZERO),
+        };
+        if name.is_empty() {
+            return None;
+        }
+        Some(TestResult {
+            name: name.to_string(),
+            status,
+            duration,
+            message: None,
+        })
+    }
+}
+
+/// `--- PASS: TestAdd (0.01s)`, printed by `go test -v`.
+struct GoTestParser;
+
+impl TestOutputParser for GoTestParser {
+    fn parse_line(&mut self, line: &str) -> Option<TestResult> {
+        let line = line.trim().strip_prefix("--- ")?;
+        let (status, rest) = line.split_once(": ")?;
+        let status = match status {
+            "PASS" => TestOutcome::Passed,
+            "FAIL" => TestOutcome::Failed,
+            "SKIP" => TestOutcome::Skipped,
+            _ => return None,
+        };
+        let (name, duration) = match rest
+            .strip_suffix("s)")
+            .and_then(|rest| rest.rsplit_once(" ("))
+            .and_then(|(name, seconds)| Some((name, seconds.parse::<f64>().ok()?)))
+        {
+            Some((name, seconds)) => (name, Duration::from_secs_f64(seconds)),
+            None => (rest, Duration::ZERO),
+        };
+        Some(TestResult {
+            name: name.to_string(),
+            status,
+            duration,
+            message: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(framework: TestFramework, output: &str) -> Vec<(String, TestOutcome, Duration)> {
+        let mut parser = parser_for(framework);
+        output
+            .lines()
+            .filter_map(|line| parser.parse_line(line))
+            .map(|result| (result.name, result.status, result.duration))
+            .collect()
+    }
+
+    #[test]
+    fn test_cargo_output() {
+        let output = "running 3 tests
+test tests::adds ... ok
+test tests::subtracts ... FAILED
+test tests::slow ... ignored, takes too long
+
+test result: FAILED. 1 passed; 1 failed; 1 ignored";
+        assert_eq!(
+            parse(TestFramework::Cargo, output),
+            vec![
+                (
+                    "tests::adds".to_string(),
+                    TestOutcome::Passed,
+                    Duration::ZERO
+                ),
+                (
+                    "tests::subtracts".to_string(),
+                    TestOutcome::Failed,
+                    Duration::ZERO
+                ),
+                (
+                    "tests::slow".to_string(),
+                    TestOutcome::Skipped,
+                    Duration::ZERO
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pytest_output() {
+        let output = "tests/test_math.py::test_add PASSED                  [ 50%]
+tests/test_math.py::test_sub FAILED                  [100%]
+FAILED tests/test_math.py::test_sub - assert 1 == 2";
+        assert_eq!(
+            parse(TestFramework::Pytest, output),
+            vec![
+                (
+                    "tests/test_math.py::test_add".to_string(),
+                    TestOutcome::Passed,
+                    Duration::ZERO
+                ),
+                (
+                    "tests/test_math.py::test_sub".to_string(),
+                    TestOutcome::Failed,
+                    Duration::ZERO
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_jest_output() {
+        let output = " PASS  src/math.test.js
+  math
+    ✓ adds numbers (3 ms)
+    ✕ subtracts numbers (12 ms)
+    ○ skipped divides numbers
+    ✔ multiplies numbers";
+        assert_eq!(
+            parse(TestFramework::Jest, output),
+            vec![
+                (
+                    "adds numbers".to_string(),
+                    TestOutcome::Passed,
+                    Duration::from_millis(3)
+                ),
+                (
+                    "subtracts numbers".to_string(),
+                    TestOutcome::Failed,
+                    Duration::from_millis(12)
+                ),
+                (
+                    "divides numbers".to_string(),
+                    TestOutcome::Skipped,
+                    Duration::ZERO
+                ),
+                (
+                    "multiplies numbers".to_string(),
+                    TestOutcome::Passed,
+                    Duration::ZERO
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_go_output() {
+        let output = "=== RUN   TestAdd
+--- PASS: TestAdd (0.25s)
+=== RUN   TestSub
+--- FAIL: TestSub (0.00s)
+    --- SKIP: TestSub/negative (0.00s)
+FAIL";
+        assert_eq!(
+            parse(TestFramework::Go, output),
+            vec![
+                (
+                    "TestAdd".to_string(),
+                    TestOutcome::Passed,
+                    Duration::from_millis(250)
+                ),
+                ("TestSub".to_string(), TestOutcome::Failed, Duration::ZERO),
+                (
+                    "TestSub/negative".to_string(),
+                    TestOutcome::Skipped,
+                    Duration::ZERO
+                ),
+            ]
+        );
+    }
+}
@@ -61,7 +61,12 @@ fn serialize_pane(pane: &View<Pane>, active: bool, cx: &WindowContext) -> Serial
         .items()
         .filter_map(|item| {
             let terminal_view = item.act_as::<TerminalView>(cx)?;
-            if terminal_view.read(cx).terminal().read(cx).task().is_some() {
+            if terminal_view
+                .read(cx)
+                .terminal()
+                .read(cx)
+                .is_task_terminal()
+            {
                 None
             } else {
                 let id = item.item_id().as_u64();
@@ -219,33 +224,35 @@ async fn deserialize_pane_group(
                 })
                 .log_err()?;
             let active_item = serialized_pane.active_item;
-            pane.update(cx, |pane, cx| {
-                populate_pane_items(pane, new_items, active_item, cx);
-                // Avoid blank panes in splits
-                if pane.items_len() == 0 {
-                    let working_directory = workspace
-                        .update(cx, |workspace, cx| default_working_directory(workspace, cx))
-                        .ok()
-                        .flatten();
-                    let kind = TerminalKind::Shell(working_directory);
-                    let window = cx.window_handle();
-                    let terminal = project
-                        .update(cx, |project, cx| project.create_terminal(kind, window, cx))
-                        .log_err()?;
+            let new_terminal = pane
+                .update(cx, |pane, cx| {
+                    populate_pane_items(pane, new_items, active_item, cx);
+                    // Avoid blank panes in splits
+                    if pane.items_len() == 0 {
+                        let working_directory = workspace
+                            .update(cx, |workspace, cx| default_working_directory(workspace, cx))
+                            .ok()
+                            .flatten();
+                        let kind = TerminalKind::Shell(working_directory);
+                        let window = cx.window_handle();
+                        Some(project.update(cx, |project, cx| {
+                            project.create_terminal(kind, None, window, cx)
+                        }))
+                    } else {
+                        None
+                    }
+                })
+                .ok()?;
+            if let Some(new_terminal) = new_terminal {
+                let terminal = new_terminal.await.log_err()?;
+                pane.update(cx, |pane, cx| {
                     let terminal_view = Box::new(cx.new_view(|cx| {
-                        TerminalView::new(
-                            terminal.clone(),
-                            workspace.clone(),
-                            Some(workspace_id),
-                            cx,
-                        )
+                        TerminalView::new(terminal, workspace.clone(), Some(workspace_id), cx)
                     }));
                     pane.add_item(terminal_view, true, false, None, cx);
-                }
-                Some(())
-            })
-            .ok()
-            .flatten()?;
+                })
+                .ok()?;
+            }
             Some((Member::Pane(pane.clone()), active.then_some(pane)))
         }
     }
@@ -76,11 +76,17 @@ impl DisplayCursor {
 pub struct LayoutCell {
     pub point: AlacPoint<i32, i32>,
     text: gpui::ShapedLine,
+    /// More than one for the ligatures, which glyph is centered across all of their cells.
+    num_of_cells: usize,
 }
 
 impl LayoutCell {
-    fn new(point: AlacPoint<i32, i32>, text: gpui::ShapedLine) -> LayoutCell {
-        LayoutCell { point, text }
+    fn new(point: AlacPoint<i32, i32>, text: gpui::ShapedLine, num_of_cells: usize) -> LayoutCell {
+        LayoutCell {
+            point,
+            text,
+            num_of_cells,
+        }
     }
 
     pub fn paint(
@@ -92,9 +98,15 @@ impl LayoutCell {
     ) {
         let pos = {
             let point = self.point;
+            let ligature_offset = if self.num_of_cells > 1 {
+                ((dimensions.cell_width * self.num_of_cells as f32 - self.text.width) * 0.5)
+                    .max(Pixels::ZERO)
+            } else {
+                Pixels::ZERO
+            };
 
             Point::new(
-                (origin.x + point.column as f32 * dimensions.cell_width).floor(),
+                (origin.x + point.column as f32 * dimensions.cell_width + ligature_offset).floor(),
                 origin.y + point.line as f32 * dimensions.line_height,
             )
         };
@@ -202,6 +214,7 @@ impl TerminalElement {
         // terminal_theme: &TerminalStyle,
         text_system: &WindowTextSystem,
         hyperlink: Option<(HighlightStyle, &RangeInclusive<AlacPoint>)>,
+        ligatures: bool,
         cx: &WindowContext,
     ) -> (Vec<LayoutCell>, Vec<LayoutRect>) {
         let theme = cx.theme();
@@ -213,7 +226,10 @@ impl TerminalElement {
 
         let linegroups = grid.into_iter().chunk_by(|i| i.point.line);
         for (line_index, (_, line)) in linegroups.into_iter().enumerate() {
-            for cell in line {
+            let line = line.collect::<Vec<_>>();
+            // The cells up to this index have their text laid out as a part of a ligature already.
+            let mut ligature_end = 0;
+            for (ix, cell) in line.iter().enumerate() {
                 let mut fg = cell.fg;
                 let mut bg = cell.bg;
                 if cell.flags.contains(Flags::INVERSE) {
@@ -278,10 +294,29 @@ impl TerminalElement {
 
                 //Layout current cell text
                 {
-                    if !is_blank(&cell) {
-                        let cell_text = cell.c.to_string();
+                    if ix >= ligature_end && !is_blank(cell) {
+                        let num_of_cells = if ligatures {
+                            // A ligature is rendered with a single style, so all of its cells need to share it.
+                            ligature_len(
+                                line[ix..]
+                                    .iter()
+                                    .take_while(|other| {
+                                        other.fg == cell.fg
+                                            && other.bg == cell.bg
+                                            && other.flags == cell.flags
+                                    })
+                                    .map(|other| other.c),
+                            )
+                        } else {
+                            1
+                        };
+                        ligature_end = ix + num_of_cells;
+                        let cell_text = line[ix..ligature_end]
+                            .iter()
+                            .map(|cell| cell.c)
+                            .collect::<String>();
                         let cell_style =
-                            TerminalElement::cell_style(&cell, fg, theme, text_style, hyperlink);
+                            TerminalElement::cell_style(cell, fg, theme, text_style, hyperlink);
 
                         let layout_cell = text_system
                             .shape_line(
@@ -294,6 +329,7 @@ impl TerminalElement {
                         cells.push(LayoutCell::new(
                             AlacPoint::new(line_index as i32, cell.point.column.0 as i32),
                             layout_cell,
+                            num_of_cells,
                         ))
                     };
                 }
@@ -376,7 +412,7 @@ impl TerminalElement {
         let style = if flags.intersects(Flags::ITALIC) {
             FontStyle::Italic
         } else {
-            FontStyle::Normal
+            text_style.font_style
         };
 
         let mut result = TextRun {
@@ -630,7 +666,13 @@ impl Element for TerminalElement {
                     .unwrap_or(&settings.buffer_font.features)
                     .clone();
 
-                let font_weight = terminal_settings.font_weight.unwrap_or_default();
+                let font_weight = terminal_settings
+                    .font_weight
+                    .unwrap_or(settings.buffer_font.weight);
+
+                let font_style = terminal_settings
+                    .font_style
+                    .unwrap_or(settings.buffer_font.style);
 
                 let line_height = terminal_settings.line_height.value();
                 let font_size = terminal_settings.font_size;
@@ -660,7 +702,7 @@ impl Element for TerminalElement {
                     font_weight,
                     font_fallbacks,
                     font_size: font_size.into(),
-                    font_style: FontStyle::Normal,
+                    font_style,
                     line_height: line_height.into(),
                     background_color: Some(theme.colors().terminal_ansi_background),
                     white_space: WhiteSpace::Normal,
@@ -759,6 +801,7 @@ impl Element for TerminalElement {
                     last_hovered_word
                         .as_ref()
                         .map(|last_hovered_word| (link_style, &last_hovered_word.word_match)),
+                    TerminalSettings::get_global(cx).ligatures,
                     cx,
                 );
 
@@ -1051,6 +1094,21 @@ impl InputHandler for TerminalInputHandler {
     }
 }
 
+/// The programming ligatures the fonts like Fira Code or Cascadia Code render as single glyphs, longest first.
+const LIGATURES: &[&str] = &[
+    "===", "!==", "<=>", "...", "->", "=>", "<-", "!=", "==", ">=", "<=", "::", "&&", "||", "|>",
+    "<|", "++", "..", "//", "/*", "*/", "<<", ">>",
+];
+
+/// The number of the characters from the start that form a known ligature, or 1.
+fn ligature_len(chars: impl Iterator<Item = char>) -> usize {
+    let prefix = chars.take(3).collect::<String>();
+    LIGATURES
+        .iter()
+        .find(|ligature| prefix.starts_with(*ligature))
+        .map_or(1, |ligature| ligature.len())
+}
+
 pub fn is_blank(cell: &IndexedCell) -> bool {
     if cell.c != ' ' {
         return false;
@@ -12,9 +12,10 @@ use collections::HashMap;
 use db::kvp::KEY_VALUE_STORE;
 use futures::future::join_all;
 use gpui::{
-    actions, Action, AnchorCorner, AnyView, AppContext, AsyncWindowContext, EventEmitter,
-    ExternalPaths, FocusHandle, FocusableView, IntoElement, Model, ParentElement, Pixels, Render,
-    Styled, Task, View, ViewContext, VisualContext, WeakView, WindowContext,
+    actions, percentage, Action, AnchorCorner, Animation, AnimationExt, AnyView, AppContext,
+    AsyncWindowContext, Axis, EventEmitter, ExternalPaths, FocusHandle, FocusableView, IntoElement,
+    Model, ParentElement, Pixels, Render, Styled, Task, Transformation, View, ViewContext,
+    VisualContext, WeakView, WindowContext,
 };
 use itertools::Itertools;
 use project::{terminals::TerminalKind, Fs, Project, ProjectEntryId};
@@ -22,12 +23,13 @@ use search::{buffer_search::DivRegistrar, BufferSearchBar};
 use settings::Settings;
 use task::{RevealStrategy, Shell, SpawnInTerminal, TaskId};
 use terminal::{
-    terminal_settings::{TerminalDockPosition, TerminalSettings},
-    Terminal,
+    terminal_settings::{PanelOrientation, TerminalDockPosition, TerminalSettings},
+    FocusNextFailed, FocusNextRunning, TaskStatus, Terminal,
 };
 use ui::{
-    div, h_flex, ButtonCommon, Clickable, ContextMenu, FluentBuilder, IconButton, IconSize,
-    InteractiveElement, PopoverMenu, Selectable, Tooltip,
+    div, h_flex, v_flex, Button, ButtonCommon, Clickable, Color, ContextMenu, FluentBuilder, Icon,
+    IconButton, IconSize, InteractiveElement, KeyBinding, Label, LabelCommon, LabelSize,
+    PopoverMenu, Selectable, Tooltip,
 };
 use util::{ResultExt, TryFutureExt};
 use workspace::{
@@ -52,6 +54,8 @@ pub fn init(cx: &mut AppContext) {
         |workspace: &mut Workspace, _: &mut ViewContext<Workspace>| {
             workspace.register_action(TerminalPanel::new_terminal);
             workspace.register_action(TerminalPanel::open_terminal);
+            workspace.register_action(TerminalPanel::focus_next_failed);
+            workspace.register_action(TerminalPanel::focus_next_running);
             workspace.register_action(|workspace, _: &ToggleFocus, cx| {
                 if workspace
                     .panel::<TerminalPanel>(cx)
@@ -79,6 +83,8 @@ pub struct TerminalPanel {
     enabled: bool,
     assistant_enabled: bool,
     assistant_tab_bar_button: Option<AnyView>,
+    /// Where the panel got docked for a task, instead of the `dock` setting.
+    position_override: Option<DockPosition>,
 }
 
 impl TerminalPanel {
@@ -87,6 +93,10 @@ impl TerminalPanel {
         let pane = new_terminal_pane(workspace.weak_handle(), project.clone(), cx);
         let center = PaneGroup::new(pane.clone());
         let enabled = project.read(cx).supports_terminal(cx);
+        // Keep the ssh connection's round-trip time shown in the tab bar up to date.
+        if let Some(ssh_client) = project.read(cx).ssh_client() {
+            cx.observe(&ssh_client, |_, _, cx| cx.notify()).detach();
+        }
         let terminal_panel = Self {
             center,
             active_pane: pane,
@@ -100,6 +110,7 @@ impl TerminalPanel {
             enabled,
             assistant_enabled: false,
             assistant_tab_bar_button: None,
+            position_override: None,
         };
         terminal_panel.apply_tab_bar_buttons(&terminal_panel.active_pane, cx);
         terminal_panel
@@ -128,6 +139,7 @@ impl TerminalPanel {
 
     fn apply_tab_bar_buttons(&self, terminal_pane: &View<Pane>, cx: &mut ViewContext<Self>) {
         let assistant_tab_bar_button = self.assistant_tab_bar_button.clone();
+        let workspace = self.workspace.clone();
         terminal_pane.update(cx, |pane, cx| {
             pane.set_render_tab_bar_buttons(cx, move |pane, cx| {
                 let split_context = pane
@@ -137,9 +149,32 @@ impl TerminalPanel {
                 if !pane.has_focus(cx) && !pane.context_menu_focused(cx) {
                     return (None, None);
                 }
+                let ssh_status = pane
+                    .active_item()
+                    .and_then(|item| item.downcast::<TerminalView>())
+                    .filter(|terminal_view| {
+                        terminal_view.read(cx).terminal().read(cx).is_ssh_terminal()
+                    })
+                    .and_then(|_| {
+                        let workspace = workspace.upgrade()?;
+                        let (host, rtt_ms) = workspace
+                            .read(cx)
+                            .project()
+                            .read(cx)
+                            .ssh_connection_rtt(cx)?;
+                        Some(match rtt_ms {
+                            Some(rtt_ms) => format!("SSH: {host} [RTT: {rtt_ms}ms]"),
+                            None => format!("SSH: {host}"),
+                        })
+                    });
                 let focus_handle = pane.focus_handle(cx);
                 let right_children = h_flex()
                     .gap_2()
+                    .children(ssh_status.map(|ssh_status| {
+                        Label::new(ssh_status)
+                            .size(LabelSize::Small)
+                            .color(Color::Muted)
+                    }))
                     .children(assistant_tab_bar_button.clone())
                     .child(
                         PopoverMenu::new("terminal-tab-bar-popover-menu")
@@ -318,10 +353,22 @@ impl TerminalPanel {
                 }
             }
             pane::Event::Split(direction) => {
-                let Some(new_pane) = self.new_pane_with_cloned_active_terminal(cx) else {
-                    return;
-                };
-                self.center.split(&pane, &new_pane, *direction).log_err();
+                let new_pane = self.new_pane_with_cloned_active_terminal(cx);
+                let pane = pane.clone();
+                let direction = *direction;
+                cx.spawn(move |terminal_panel, mut cx| async move {
+                    let Some(new_pane) = new_pane.await? else {
+                        return Ok(());
+                    };
+                    terminal_panel.update(&mut cx, |terminal_panel, cx| {
+                        terminal_panel
+                            .center
+                            .split(&pane, &new_pane, direction)
+                            .log_err();
+                        cx.notify();
+                    })
+                })
+                .detach_and_log_err(cx);
             }
             pane::Event::Focus => {
                 self.active_pane = pane.clone();
@@ -334,8 +381,10 @@ impl TerminalPanel {
     fn new_pane_with_cloned_active_terminal(
         &mut self,
         cx: &mut ViewContext<Self>,
-    ) -> Option<View<Pane>> {
-        let workspace = self.workspace.clone().upgrade()?;
+    ) -> Task<Result<Option<View<Pane>>>> {
+        let Some(workspace) = self.workspace.clone().upgrade() else {
+            return Task::ready(Ok(None));
+        };
         let project = workspace.read(cx).project().clone();
         let working_directory = self
             .active_pane
@@ -352,21 +401,26 @@ impl TerminalPanel {
             .or_else(|| default_working_directory(workspace.read(cx), cx));
         let kind = TerminalKind::Shell(working_directory);
         let window = cx.window_handle();
-        let terminal = project
-            .update(cx, |project, cx| project.create_terminal(kind, window, cx))
-            .log_err()?;
-        let database_id = workspace.read(cx).database_id();
-        let terminal_view = Box::new(cx.new_view(|cx| {
-            TerminalView::new(terminal.clone(), self.workspace.clone(), database_id, cx)
-        }));
-        let pane = new_terminal_pane(self.workspace.clone(), project, cx);
-        self.apply_tab_bar_buttons(&pane, cx);
-        pane.update(cx, |pane, cx| {
-            pane.add_item(terminal_view, true, true, None, cx);
+        let terminal = project.update(cx, |project, cx| {
+            project.create_terminal(kind, None, window, cx)
         });
-        cx.focus_view(&pane);
-
-        Some(pane)
+        let database_id = workspace.read(cx).database_id();
+        cx.spawn(|terminal_panel, mut cx| async move {
+            let terminal = terminal.await?;
+            terminal_panel.update(&mut cx, |terminal_panel, cx| {
+                let workspace = terminal_panel.workspace.clone();
+                let terminal_view = Box::new(cx.new_view(|cx| {
+                    TerminalView::new(terminal, workspace.clone(), database_id, cx)
+                }));
+                let pane = new_terminal_pane(workspace, project, cx);
+                terminal_panel.apply_tab_bar_buttons(&pane, cx);
+                pane.update(cx, |pane, cx| {
+                    pane.add_item(terminal_view, true, true, None, cx);
+                });
+                cx.focus_view(&pane);
+                Some(pane)
+            })
+        })
     }
 
     pub fn open_terminal(
@@ -390,6 +444,7 @@ impl TerminalPanel {
     }
 
     fn spawn_task(&mut self, spawn_in_terminal: &SpawnInTerminal, cx: &mut ViewContext<Self>) {
+        self.dock_for_task(cx);
         let mut spawn_task = spawn_in_terminal.clone();
         // Set up shell args unconditionally, as tasks are always spawned inside of a shell.
         let Some((shell, mut user_args)) = (match spawn_in_terminal.shell.clone() {
@@ -410,6 +465,9 @@ impl TerminalPanel {
             }
             Shell::Program(shell) => Some((shell, Vec::new())),
             Shell::WithArguments { program, args, .. } => Some((program, args)),
+            Shell::Login { program } => program
+                .or_else(retrieve_system_shell)
+                .map(|shell| (shell, vec!["-l".to_string()])),
         }) else {
             return;
         };
@@ -500,7 +558,8 @@ impl TerminalPanel {
                 existing_item_index,
                 existing_terminal,
                 cx,
-            );
+            )
+            .detach();
         } else {
             self.deferred_tasks.insert(
                 spawn_in_terminal.id.clone(),
@@ -513,13 +572,15 @@ impl TerminalPanel {
                                     .spawn_in_new_terminal(spawn_task, cx)
                                     .detach_and_log_err(cx);
                             } else {
-                                terminal_panel.replace_terminal(
-                                    spawn_task,
-                                    task_pane,
-                                    existing_item_index,
-                                    existing_terminal,
-                                    cx,
-                                );
+                                terminal_panel
+                                    .replace_terminal(
+                                        spawn_task,
+                                        task_pane,
+                                        existing_item_index,
+                                        existing_terminal,
+                                        cx,
+                                    )
+                                    .detach();
                             }
                         })
                         .ok();
@@ -528,6 +589,43 @@ impl TerminalPanel {
         }
     }
 
+    /// Docks the panel according to the `preferred_panel_orientation` setting.
+    /// The `dock` setting is left as is: the panel returns there once the user moves it.
+    fn dock_for_task(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let position = match TerminalSettings::get_global(cx).preferred_panel_orientation {
+            PanelOrientation::Docked => return,
+            PanelOrientation::Horizontal => DockPosition::Bottom,
+            PanelOrientation::Vertical => DockPosition::Right,
+            PanelOrientation::Auto => match editors_split_axis(workspace.read(cx)) {
+                Some(Axis::Horizontal) => DockPosition::Right,
+                Some(Axis::Vertical) => DockPosition::Bottom,
+                None => return,
+            },
+        };
+        if self.position(cx) == position {
+            return;
+        }
+        self.position_override = Some(position);
+
+        // The docks update the panel when it is removed from them, so it is moved after this update.
+        let panel = cx.view().clone();
+        cx.window_context().defer(move |cx| {
+            workspace.update(cx, |workspace, cx| {
+                for dock in [
+                    workspace.left_dock(),
+                    workspace.bottom_dock(),
+                    workspace.right_dock(),
+                ] {
+                    dock.update(cx, |dock, cx| dock.remove_panel(&panel, cx));
+                }
+                workspace.add_panel(panel, cx);
+            });
+        });
+    }
+
     pub fn spawn_in_new_terminal(
         &mut self,
         spawn_task: SpawnInTerminal,
@@ -556,6 +654,106 @@ impl TerminalPanel {
             .detach_and_log_err(cx);
     }
 
+    fn focus_next_failed(
+        workspace: &mut Workspace,
+        _: &FocusNextFailed,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        Self::focus_next_task_terminal(
+            workspace,
+            |status| {
+                matches!(
+                    status,
+                    TaskStatus::Completed { success: false } | TaskStatus::Crashed { .. }
+                )
+            },
+            cx,
+        );
+    }
+
+    fn focus_next_running(
+        workspace: &mut Workspace,
+        _: &FocusNextRunning,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        Self::focus_next_task_terminal(workspace, |status| status == TaskStatus::Running, cx);
+    }
+
+    /// Focuses the next terminal after the active one, in the order the terminals were created,
+    /// whose task status matches, wrapping around.
+    fn focus_next_task_terminal(
+        workspace: &mut Workspace,
+        status_matches: impl Fn(TaskStatus) -> bool,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        let Some(terminal_panel) = workspace.panel::<Self>(cx) else {
+            return;
+        };
+        let terminal_locations = terminal_panel
+            .read(cx)
+            .center
+            .panes()
+            .into_iter()
+            .flat_map(|pane| {
+                pane.read(cx)
+                    .items()
+                    .enumerate()
+                    .filter_map(|(index, item)| Some((index, item.act_as::<TerminalView>(cx)?)))
+                    .map(|(index, terminal_view)| {
+                        let terminal_id = terminal_view.read(cx).terminal().entity_id();
+                        (terminal_id, (pane.clone(), index))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<HashMap<_, _>>();
+        let active_terminal_id = terminal_panel
+            .read(cx)
+            .active_pane
+            .read(cx)
+            .active_item()
+            .and_then(|item| item.act_as::<TerminalView>(cx))
+            .map(|terminal_view| terminal_view.read(cx).terminal().entity_id());
+
+        // Cycle through the live terminals in their creation order, the released ones are skipped.
+        let mut terminals = workspace
+            .project()
+            .read(cx)
+            .live_terminals()
+            .collect::<Vec<_>>();
+        terminals.sort_by_key(|terminal| terminal.read(cx).created_at());
+        let active_ix = active_terminal_id
+            .and_then(|active_id| {
+                terminals
+                    .iter()
+                    .position(|terminal| terminal.entity_id() == active_id)
+            })
+            .unwrap_or(terminals.len());
+        let matching_terminals = terminals
+            .iter()
+            .enumerate()
+            .filter(|(_, terminal)| {
+                terminal
+                    .read(cx)
+                    .task()
+                    .is_some_and(|task| status_matches(task.status))
+            })
+            .filter_map(|(ix, terminal)| Some((ix, terminal_locations.get(&terminal.entity_id())?)))
+            .collect::<Vec<_>>();
+        let Some((_, (pane, item_index))) = matching_terminals
+            .iter()
+            .find(|(ix, _)| *ix > active_ix)
+            .or_else(|| matching_terminals.first())
+        else {
+            return;
+        };
+
+        let (pane, item_index) = (pane.clone(), *item_index);
+        terminal_panel.update(cx, |terminal_panel, cx| {
+            terminal_panel.activate_terminal_view(&pane, item_index, true, cx)
+        });
+        workspace.focus_panel::<Self>(cx);
+    }
+
     fn terminals_for_task(
         &self,
         label: &str,
@@ -611,11 +809,16 @@ impl TerminalPanel {
 
         cx.spawn(|terminal_panel, mut cx| async move {
             let pane = terminal_panel.update(&mut cx, |this, _| this.active_pane.clone())?;
+            let terminal = workspace
+                .update(&mut cx, |workspace, cx| {
+                    let window = cx.window_handle();
+                    workspace.project().update(cx, |project, cx| {
+                        project.create_terminal(kind, None, window, cx)
+                    })
+                })?
+                .await;
             let result = workspace.update(&mut cx, |workspace, cx| {
-                let window = cx.window_handle();
-                let terminal = workspace
-                    .project()
-                    .update(cx, |project, cx| project.create_terminal(kind, window, cx))?;
+                let terminal = terminal?;
                 let terminal_view = Box::new(cx.new_view(|cx| {
                     TerminalView::new(
                         terminal.clone(),
@@ -695,59 +898,126 @@ impl TerminalPanel {
         terminal_item_index: usize,
         terminal_to_replace: View<TerminalView>,
         cx: &mut ViewContext<'_, Self>,
-    ) -> Option<()> {
-        let project = self
-            .workspace
-            .update(cx, |workspace, _| workspace.project().clone())
-            .ok()?;
-
+    ) -> Task<Option<()>> {
         let reveal = spawn_task.reveal;
         let window = cx.window_handle();
-        let new_terminal = project.update(cx, |project, cx| {
-            project
-                .create_terminal(TerminalKind::Task(spawn_task), window, cx)
-                .log_err()
-        })?;
-        terminal_to_replace.update(cx, |terminal_to_replace, cx| {
-            terminal_to_replace.set_terminal(new_terminal, cx);
-        });
+        let task_workspace = self.workspace.clone();
+        cx.spawn(move |terminal_panel, mut cx| async move {
+            let project = task_workspace
+                .update(&mut cx, |workspace, _| workspace.project().clone())
+                .ok()?;
+            let new_terminal = project
+                .update(&mut cx, |project, cx| {
+                    project.create_terminal(TerminalKind::Task(spawn_task), None, window, cx)
+                })
+                .ok()?
+                .await
+                .log_err()?;
+            terminal_to_replace
+                .update(&mut cx, |terminal_to_replace, cx| {
+                    terminal_to_replace.set_terminal(new_terminal, cx);
+                })
+                .ok()?;
 
-        match reveal {
-            RevealStrategy::Always => {
-                self.activate_terminal_view(&task_pane, terminal_item_index, true, cx);
-                let task_workspace = self.workspace.clone();
-                cx.spawn(|_, mut cx| async move {
+            match reveal {
+                RevealStrategy::Always => {
+                    terminal_panel
+                        .update(&mut cx, |terminal_panel, cx| {
+                            terminal_panel.activate_terminal_view(
+                                &task_pane,
+                                terminal_item_index,
+                                true,
+                                cx,
+                            )
+                        })
+                        .ok()?;
                     task_workspace
                         .update(&mut cx, |workspace, cx| workspace.focus_panel::<Self>(cx))
-                        .ok()
-                })
-                .detach();
-            }
-            RevealStrategy::NoFocus => {
-                self.activate_terminal_view(&task_pane, terminal_item_index, false, cx);
-                let task_workspace = self.workspace.clone();
-                cx.spawn(|_, mut cx| async move {
+                        .ok()?;
+                }
+                RevealStrategy::NoFocus => {
+                    terminal_panel
+                        .update(&mut cx, |terminal_panel, cx| {
+                            terminal_panel.activate_terminal_view(
+                                &task_pane,
+                                terminal_item_index,
+                                false,
+                                cx,
+                            )
+                        })
+                        .ok()?;
                     task_workspace
                         .update(&mut cx, |workspace, cx| workspace.open_panel::<Self>(cx))
-                        .ok()
-                })
-                .detach();
+                        .ok()?;
+                }
+                RevealStrategy::Never => {}
             }
-            RevealStrategy::Never => {}
-        }
 
-        Some(())
+            Some(())
+        })
     }
 
     fn has_no_terminals(&self, cx: &WindowContext) -> bool {
         self.active_pane.read(cx).items_len() == 0 && self.pending_terminals_to_add == 0
     }
 
+    /// Shown instead of the empty pane: a way to open a terminal,
+    /// or a spinner while the terminals being opened (e.g. for a task) are not there yet.
+    fn render_placeholder(&self, cx: &WindowContext) -> impl IntoElement {
+        let content = if self.pending_terminals_to_add > 0 {
+            Icon::new(IconName::ArrowCircle)
+                .size(IconSize::Medium)
+                .color(Color::Muted)
+                .with_animation(
+                    "pending-terminal",
+                    Animation::new(Duration::from_secs(2)).repeat(),
+                    |icon, delta| icon.transform(Transformation::rotate(percentage(delta))),
+                )
+                .into_any_element()
+        } else {
+            v_flex()
+                .gap_2()
+                .items_center()
+                .child(Label::new("No terminals open").color(Color::Muted))
+                .child(
+                    Button::new("new-terminal", "New Terminal")
+                        .key_binding(KeyBinding::for_action(&NewTerminal, cx))
+                        .on_click(|_, cx| cx.dispatch_action(NewTerminal.boxed_clone())),
+                )
+                .into_any_element()
+        };
+        v_flex()
+            .size_full()
+            .items_center()
+            .justify_center()
+            .child(content)
+    }
+
     pub fn assistant_enabled(&self) -> bool {
         self.assistant_enabled
     }
 }
 
+/// How the workspace's editors are split: side by side ([`Axis::Horizontal`])
+/// or stacked ([`Axis::Vertical`]). `None` if there is a single editor pane, or the splits are mixed.
+fn editors_split_axis(workspace: &Workspace) -> Option<Axis> {
+    let bounds = workspace
+        .panes()
+        .iter()
+        .filter_map(|pane| workspace.bounding_box_for_pane(pane))
+        .collect::<Vec<_>>();
+    let (first, rest) = bounds.split_first()?;
+    if rest.is_empty() {
+        None
+    } else if rest.iter().all(|bounds| bounds.origin.y == first.origin.y) {
+        Some(Axis::Horizontal)
+    } else if rest.iter().all(|bounds| bounds.origin.x == first.origin.x) {
+        Some(Axis::Vertical)
+    } else {
+        None
+    }
+}
+
 pub fn new_terminal_pane(
     workspace: WeakView<Workspace>,
     project: Model<Project>,
@@ -767,6 +1037,7 @@ pub fn new_terminal_pane(
         pane.set_can_navigate(false, cx);
         pane.display_nav_history_buttons(None);
         pane.set_should_display_tab_bar(|_| true);
+        pane.set_zoom_on_tab_double_click(true);
 
         let terminal_panel_for_split_check = terminal_panel.clone();
         pane.set_can_split(Some(Arc::new(move |pane, dragged_item, cx| {
@@ -937,15 +1208,24 @@ impl Render for TerminalPanel {
         let registrar = registrar.into_div();
         self.workspace
             .update(cx, |workspace, cx| {
-                registrar.size_full().child(self.center.render(
-                    workspace.project(),
-                    &HashMap::default(),
-                    None,
-                    &self.active_pane,
-                    workspace.zoomed_item(),
-                    workspace.app_state(),
-                    cx,
-                ))
+                let content = if self.center.panes().len() == 1
+                    && self.active_pane.read(cx).items_len() == 0
+                {
+                    self.render_placeholder(cx).into_any_element()
+                } else {
+                    self.center
+                        .render(
+                            workspace.project(),
+                            &HashMap::default(),
+                            None,
+                            &self.active_pane,
+                            workspace.zoomed_item(),
+                            workspace.app_state(),
+                            cx,
+                        )
+                        .into_any_element()
+                };
+                registrar.size_full().child(content)
             })
             .ok()
             .map(|div| {
@@ -998,18 +1278,21 @@ impl Render for TerminalPanel {
                     if let Some(pane) = panes.get(action.0).map(|p| (*p).clone()) {
                         cx.focus_view(&pane);
                     } else {
-                        if let Some(new_pane) =
-                            terminal_panel.new_pane_with_cloned_active_terminal(cx)
-                        {
-                            terminal_panel
-                                .center
-                                .split(
-                                    &terminal_panel.active_pane,
-                                    &new_pane,
-                                    SplitDirection::Right,
-                                )
-                                .log_err();
-                        }
+                        let active_pane = terminal_panel.active_pane.clone();
+                        let new_pane = terminal_panel.new_pane_with_cloned_active_terminal(cx);
+                        cx.spawn(|terminal_panel, mut cx| async move {
+                            let Some(new_pane) = new_pane.await? else {
+                                return Ok(());
+                            };
+                            terminal_panel.update(&mut cx, |terminal_panel, cx| {
+                                terminal_panel
+                                    .center
+                                    .split(&active_pane, &new_pane, SplitDirection::Right)
+                                    .log_err();
+                                cx.notify();
+                            })
+                        })
+                        .detach_and_log_err(cx);
                     }
                 }))
                 .on_action(cx.listener(
@@ -1039,6 +1322,9 @@ impl FocusableView for TerminalPanel {
 
 impl Panel for TerminalPanel {
     fn position(&self, cx: &WindowContext) -> DockPosition {
+        if let Some(position) = self.position_override {
+            return position;
+        }
         match TerminalSettings::get_global(cx).dock {
             TerminalDockPosition::Left => DockPosition::Left,
             TerminalDockPosition::Bottom => DockPosition::Bottom,
@@ -1051,6 +1337,7 @@ impl Panel for TerminalPanel {
     }
 
     fn set_position(&mut self, position: DockPosition, cx: &mut ViewContext<Self>) {
+        self.position_override = None;
         settings::update_settings_file::<TerminalSettings>(
             self.fs.clone(),
             cx,
@@ -6,10 +6,10 @@ use collections::HashSet;
 use editor::{actions::SelectAll, scroll::Autoscroll, Editor};
 use futures::{stream::FuturesUnordered, StreamExt};
 use gpui::{
-    anchored, deferred, div, impl_actions, AnyElement, AppContext, DismissEvent, EventEmitter,
-    FocusHandle, FocusableView, KeyContext, KeyDownEvent, Keystroke, Model, MouseButton,
-    MouseDownEvent, Pixels, Render, ScrollWheelEvent, Styled, Subscription, Task, View,
-    VisualContext, WeakView,
+    anchored, deferred, div, impl_actions, AnyElement, AppContext, ClipboardItem, DismissEvent,
+    EventEmitter, FocusHandle, FocusableView, KeyContext, KeyDownEvent, Keystroke, Model,
+    MouseButton, MouseDownEvent, Pixels, Render, ScrollWheelEvent, Styled, Subscription, Task,
+    View, VisualContext, WeakView,
 };
 use language::Bias;
 use persistence::TERMINAL_DB;
@@ -20,17 +20,22 @@ use terminal::{
         term::{search::RegexSearch, TermMode},
     },
     terminal_settings::{CursorShape, TerminalBlink, TerminalSettings, WorkingDirectory},
-    Clear, Copy, Event, MaybeNavigationTarget, Paste, ScrollLineDown, ScrollLineUp, ScrollPageDown,
-    ScrollPageUp, ScrollToBottom, ScrollToTop, ShowCharacterPalette, TaskStatus, Terminal,
-    TerminalSize, ToggleViMode,
+    Clear, ClearScrollback, Copy, Event, ExportScrollback, MaybeNavigationTarget, Paste,
+    ScrollLineDown, ScrollLineUp, ScrollPageDown, ScrollPageUp, ScrollToBottom, ScrollToTop,
+    ShowCharacterPalette, ShowFullOutput, ShowOutputDiff, TaskState, TaskStatus, Terminal,
+    TerminalSize, ToggleViMode, COMMON_SIGNALS, SSH_RECONNECT_MAX_ATTEMPTS,
+    SSH_RECONNECT_MAX_DELAY, TASK_RESTART_DELAY,
 };
 use terminal_element::{is_blank, TerminalElement};
 use terminal_panel::TerminalPanel;
 use ui::{h_flex, prelude::*, ContextMenu, Icon, IconName, Label, Tooltip};
-use util::{paths::PathWithPosition, ResultExt};
+use util::{
+    paths::{home_dir, PathWithPosition},
+    ResultExt,
+};
 use workspace::{
     item::{BreadcrumbText, Item, ItemEvent, SerializableItem, TabContentParams},
-    notifications::NotifyResultExt,
+    notifications::NotifyTaskExt,
     register_serializable_item,
     searchable::{SearchEvent, SearchOptions, SearchableItem, SearchableItemHandle},
     CloseActiveItem, NewCenterTerminal, NewTerminal, OpenVisible, ToolbarItemLocation, Workspace,
@@ -49,7 +54,7 @@ use std::{
     path::{Path, PathBuf},
     rc::Rc,
     sync::Arc,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 const REGEX_SPECIAL_CHARS: &[char] = &[
@@ -70,7 +75,11 @@ pub struct SendText(String);
 #[derive(Clone, Debug, Default, Deserialize, PartialEq)]
 pub struct SendKeystroke(String);
 
-impl_actions!(terminal, [SendText, SendKeystroke]);
+/// Sends the given signal to the foreground process of the terminal.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct SendSignal(i32);
+
+impl_actions!(terminal, [SendText, SendKeystroke, SendSignal]);
 
 pub fn init(cx: &mut AppContext) {
     terminal_panel::init(cx);
@@ -94,6 +103,15 @@ pub struct BlockContext<'a, 'b> {
     pub dimensions: TerminalSize,
 }
 
+/// The state of reconnecting the disconnected SSH terminal of a view.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SshReconnect {
+    /// Waiting for the next attempt to reconnect.
+    Pending,
+    /// [`SSH_RECONNECT_MAX_ATTEMPTS`] attempts in a row have failed, or the terminal could not be created.
+    GaveUp,
+}
+
 ///A terminal view, maintains the PTY's file handles and communicates with the terminal
 pub struct TerminalView {
     terminal: Model<Terminal>,
@@ -112,6 +130,13 @@ pub struct TerminalView {
     show_breadcrumbs: bool,
     block_below_cursor: Option<Rc<BlockProperties>>,
     scroll_top: Pixels,
+    /// The number of the current attempt in a row to reconnect the disconnected SSH terminal.
+    ssh_reconnect_attempt: usize,
+    ssh_reconnect: Option<SshReconnect>,
+    /// When the SSH terminal was last reconnected, if ever.
+    ssh_reconnected_at: Option<Instant>,
+    process_name_poll_interval_ms: u64,
+    _process_name_poll: Task<anyhow::Result<()>>,
     _subscriptions: Vec<Subscription>,
     _terminal_subscriptions: Vec<Subscription>,
 }
@@ -136,24 +161,25 @@ impl TerminalView {
         let working_directory = default_working_directory(workspace, cx);
 
         let window = cx.window_handle();
-        let terminal = workspace
-            .project()
-            .update(cx, |project, cx| {
-                project.create_terminal(TerminalKind::Shell(working_directory), window, cx)
+        let terminal = workspace.project().update(cx, |project, cx| {
+            project.create_terminal(TerminalKind::Shell(working_directory), None, window, cx)
+        });
+
+        cx.spawn(|workspace, mut cx| async move {
+            let terminal = terminal.await?;
+            workspace.update(&mut cx, |workspace, cx| {
+                let view = cx.new_view(|cx| {
+                    TerminalView::new(
+                        terminal,
+                        workspace.weak_handle(),
+                        workspace.database_id(),
+                        cx,
+                    )
+                });
+                workspace.add_item_to_active_pane(Box::new(view), None, true, cx);
             })
-            .notify_err(workspace, cx);
-
-        if let Some(terminal) = terminal {
-            let view = cx.new_view(|cx| {
-                TerminalView::new(
-                    terminal,
-                    workspace.weak_handle(),
-                    workspace.database_id(),
-                    cx,
-                )
-            });
-            workspace.add_item_to_active_pane(Box::new(view), None, true, cx);
-        }
+        })
+        .detach_and_notify_err(cx);
     }
 
     pub fn new(
@@ -172,11 +198,12 @@ impl TerminalView {
         let focus_out = cx.on_focus_out(&focus_handle, |terminal_view, _event, cx| {
             terminal_view.focus_out(cx);
         });
-        let cursor_shape = TerminalSettings::get_global(cx)
-            .cursor_shape
-            .unwrap_or_default();
+        let cursor_shape =
+            TerminalSettings::get_global(cx).cursor_shape_for(terminal.read(cx).is_task_terminal());
+        let process_name_poll_interval_ms =
+            TerminalSettings::get_global(cx).process_name_poll_interval_ms;
 
-        Self {
+        let mut terminal_view = Self {
             terminal,
             workspace: workspace_handle,
             has_bell: false,
@@ -192,13 +219,20 @@ impl TerminalView {
             show_breadcrumbs: TerminalSettings::get_global(cx).toolbar.breadcrumbs,
             block_below_cursor: None,
             scroll_top: Pixels::ZERO,
+            ssh_reconnect_attempt: 0,
+            ssh_reconnect: None,
+            ssh_reconnected_at: None,
+            process_name_poll_interval_ms,
+            _process_name_poll: Task::ready(Ok(())),
             _subscriptions: vec![
                 focus_in,
                 focus_out,
                 cx.observe_global::<SettingsStore>(Self::settings_changed),
             ],
             _terminal_subscriptions: terminal_subscriptions,
-        }
+        };
+        terminal_view.poll_process_name(cx);
+        terminal_view
     }
 
     pub fn model(&self) -> &Model<Terminal> {
@@ -226,6 +260,20 @@ impl TerminalView {
             .map_or(false, |terminal_panel| {
                 terminal_panel.read(cx).assistant_enabled()
             });
+        let can_diff_task_output = self
+            .terminal
+            .read(cx)
+            .task()
+            .zip(self.workspace.upgrade())
+            .map_or(false, |(task, workspace)| {
+                workspace
+                    .read(cx)
+                    .project()
+                    .read(cx)
+                    .can_diff_task_output(&task.id)
+            });
+        let can_send_signals =
+            !COMMON_SIGNALS.is_empty() && !self.terminal.read(cx).is_ssh_terminal();
         let context_menu = ContextMenu::build(cx, |menu, _| {
             menu.context(self.focus_handle.clone())
                 .action("New Terminal", Box::new(NewTerminal))
@@ -234,10 +282,23 @@ impl TerminalView {
                 .action("Paste", Box::new(Paste))
                 .action("Select All", Box::new(SelectAll))
                 .action("Clear", Box::new(Clear))
+                .action("Clear Scrollback", Box::new(ClearScrollback))
+                .action("Export Scrollback", Box::new(ExportScrollback))
                 .when(assistant_enabled, |menu| {
                     menu.separator()
                         .action("Inline Assist", Box::new(InlineAssist::default()))
                 })
+                .when(can_diff_task_output, |menu| {
+                    menu.separator()
+                        .action("Show Output Diff", Box::new(ShowOutputDiff))
+                })
+                .when(can_send_signals, |menu| {
+                    COMMON_SIGNALS
+                        .iter()
+                        .fold(menu.separator(), |menu, (name, signal)| {
+                            menu.action(format!("Send {name}"), Box::new(SendSignal(*signal)))
+                        })
+                })
                 .separator()
                 .action("Close", Box::new(CloseActiveItem { save_intent: None }))
         });
@@ -261,7 +322,7 @@ impl TerminalView {
         let settings = TerminalSettings::get_global(cx);
         self.show_breadcrumbs = settings.toolbar.breadcrumbs;
 
-        let new_cursor_shape = settings.cursor_shape.unwrap_or_default();
+        let new_cursor_shape = settings.cursor_shape_for(self.terminal.read(cx).is_task_terminal());
         let old_cursor_shape = self.cursor_shape;
         if old_cursor_shape != new_cursor_shape {
             self.cursor_shape = new_cursor_shape;
@@ -270,9 +331,44 @@ impl TerminalView {
             });
         }
 
+        let process_name_poll_interval_ms = settings.process_name_poll_interval_ms;
+        if process_name_poll_interval_ms != self.process_name_poll_interval_ms {
+            self.process_name_poll_interval_ms = process_name_poll_interval_ms;
+            self.poll_process_name(cx);
+        }
+
         cx.notify();
     }
 
+    /// Checks the process in the foreground of the terminal periodically, so that the title follows it
+    /// even when the process prints nothing. The titles set by the shell itself are left as they are.
+    fn poll_process_name(&mut self, cx: &mut ViewContext<Self>) {
+        let interval = Duration::from_millis(self.process_name_poll_interval_ms);
+        if interval.is_zero() {
+            self._process_name_poll = Task::ready(Ok(()));
+            return;
+        }
+        self._process_name_poll = cx.spawn(|terminal_view, mut cx| async move {
+            loop {
+                Timer::after(interval).await;
+                let process_name = terminal_view.update(&mut cx, |terminal_view, cx| {
+                    let terminal = terminal_view.terminal.read(cx);
+                    (!terminal.is_task_terminal() && !terminal.has_shell_title())
+                        .then(|| terminal.current_process_name(cx))
+                })?;
+                let Some(process_name) = process_name else {
+                    continue;
+                };
+                let process_name = process_name.await;
+                terminal_view.update(&mut cx, |terminal_view, cx| {
+                    terminal_view.terminal.update(cx, |terminal, cx| {
+                        terminal.refresh_process_name(process_name, cx)
+                    })
+                })?;
+            }
+        });
+    }
+
     fn show_character_palette(&mut self, _: &ShowCharacterPalette, cx: &mut ViewContext<Self>) {
         if self
             .terminal
@@ -303,6 +399,12 @@ impl TerminalView {
         cx.notify();
     }
 
+    fn clear_scrollback(&mut self, _: &ClearScrollback, cx: &mut ViewContext<Self>) {
+        self.scroll_top = px(0.);
+        self.terminal.update(cx, |term, _| term.clear_scrollback());
+        cx.notify();
+    }
+
     fn max_scroll_top(&self, cx: &AppContext) -> Pixels {
         let terminal = self.terminal.read(cx);
 
@@ -431,6 +533,73 @@ impl TerminalView {
         cx.notify();
     }
 
+    fn show_full_output(&mut self, _: &ShowFullOutput, cx: &mut ViewContext<Self>) {
+        let full_output_path = self.terminal.update(cx, |term, _| term.show_full_output());
+        if let Some(full_output_path) = full_output_path {
+            self.workspace
+                .update(cx, |workspace, cx| {
+                    workspace
+                        .open_abs_path(full_output_path, true, cx)
+                        .detach_and_log_err(cx);
+                })
+                .ok();
+        }
+        cx.notify();
+    }
+
+    fn show_output_diff(&mut self, _: &ShowOutputDiff, cx: &mut ViewContext<Self>) {
+        let Some(task_id) = self.terminal.read(cx).task().map(|task| task.id.clone()) else {
+            return;
+        };
+        let Some(project) = self
+            .workspace
+            .upgrade()
+            .map(|workspace| workspace.read(cx).project().clone())
+        else {
+            return;
+        };
+        if !project.read(cx).is_local() {
+            return;
+        }
+        let Some(diff) = project.read(cx).task_output_diff(&task_id) else {
+            return;
+        };
+        let diff_language = project.read(cx).languages().language_for_name("Diff");
+        let workspace = self.workspace.clone();
+        cx.spawn(|_, mut cx| async move {
+            let diff_language = diff_language.await.log_err();
+            workspace.update(&mut cx, |workspace, cx| {
+                let buffer = project.update(cx, |project, cx| {
+                    project.create_local_buffer(&diff, diff_language, cx)
+                });
+                let editor = cx.new_view(|cx| Editor::for_buffer(buffer, Some(project), cx));
+                workspace.add_item_to_active_pane(Box::new(editor), None, true, cx);
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn export_scrollback(&mut self, _: &ExportScrollback, cx: &mut ViewContext<Self>) {
+        let directory = self
+            .terminal
+            .read(cx)
+            .working_directory()
+            .unwrap_or_else(|| home_dir().clone());
+        let new_path = cx.prompt_for_new_path(&directory);
+        let terminal = self.terminal.downgrade();
+        cx.spawn(|_, mut cx| async move {
+            let Some(path) = new_path.await.ok().transpose()?.flatten() else {
+                return Ok(());
+            };
+            terminal
+                .update(&mut cx, |terminal, cx| {
+                    terminal.export_scrollback(&path, cx)
+                })?
+                .await
+        })
+        .detach_and_log_err(cx);
+    }
+
     fn toggle_vi_mode(&mut self, _: &ToggleViMode, cx: &mut ViewContext<Self>) {
         self.terminal.update(cx, |term, _| term.toggle_vi_mode());
         cx.notify();
@@ -489,6 +658,29 @@ impl TerminalView {
         .detach();
     }
 
+    /// Summarizes the statuses of the project's task terminals in the given group, e.g. "build: 2 running, 1 failed".
+    fn task_group_summary(&self, group_id: &str, cx: &AppContext) -> Option<String> {
+        let workspace = self.workspace.upgrade()?;
+        let project = workspace.read(cx).project().read(cx);
+        let (mut running, mut failed) = (0, 0);
+        for terminal in project.live_terminals() {
+            let Some(task) = terminal.read(cx).task() else {
+                continue;
+            };
+            if task.group_id.as_deref() != Some(group_id) {
+                continue;
+            }
+            match task.status {
+                TaskStatus::Running => running += 1,
+                TaskStatus::Completed { success: false } | TaskStatus::Crashed { .. } => {
+                    failed += 1
+                }
+                TaskStatus::Completed { success: true } | TaskStatus::Unknown => {}
+            }
+        }
+        Some(format!("{group_id}: {running} running, {failed} failed"))
+    }
+
     pub fn terminal(&self) -> &Model<Terminal> {
         &self.terminal
     }
@@ -538,6 +730,10 @@ impl TerminalView {
         });
     }
 
+    fn send_signal(&mut self, signal: &SendSignal, cx: &mut ViewContext<Self>) {
+        self.terminal.read(cx).send_signal(signal.0).log_err();
+    }
+
     fn send_keystroke(&mut self, text: &SendKeystroke, cx: &mut ViewContext<Self>) {
         if let Some(keystroke) = Keystroke::parse(&text.0).log_err() {
             self.clear_bell(cx);
@@ -622,6 +818,124 @@ impl TerminalView {
         dispatch_context
     }
 
+    /// Spawns the crashed task again in this view, after a delay, if the task should be restarted.
+    fn restart_crashed_task(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(task) = self.terminal.read(cx).task() else {
+            return;
+        };
+        if !task.should_restart() {
+            return;
+        }
+        let spawn_task = task.spawned_task.clone();
+        let crash_count = task.crash_count;
+        let Some(project) = self
+            .workspace
+            .upgrade()
+            .map(|workspace| workspace.read(cx).project().clone())
+        else {
+            return;
+        };
+        let crashed_terminal = self.terminal.downgrade();
+        let window = cx.window_handle();
+        cx.spawn(|terminal_view, mut cx| async move {
+            cx.background_executor().timer(TASK_RESTART_DELAY).await;
+            // The crashed terminal might have been replaced already, e.g. by rerunning the task manually.
+            let still_crashed = terminal_view.update(&mut cx, |terminal_view, _| {
+                terminal_view.terminal.downgrade() == crashed_terminal
+            })?;
+            if !still_crashed {
+                return Ok(());
+            }
+            let terminal = project
+                .update(&mut cx, |project, cx| {
+                    project.create_terminal(TerminalKind::Task(spawn_task), None, window, cx)
+                })?
+                .await?;
+            terminal.update(&mut cx, |terminal, _| {
+                terminal.set_task_crash_count(crash_count)
+            })?;
+            terminal_view.update(&mut cx, |terminal_view, cx| {
+                terminal_view.set_terminal(terminal, cx);
+                cx.notify();
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Opens a new SSH connection in this view, after the connection of its terminal was lost.
+    /// The attempts in a row back off exponentially, see [`terminal::ssh_reconnect_delay`].
+    fn reconnect_ssh(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(project) = self
+            .workspace
+            .upgrade()
+            .map(|workspace| workspace.read(cx).project().clone())
+        else {
+            return;
+        };
+        // A connection that has lived long enough is a fresh start, rather than a failed attempt.
+        if self.ssh_reconnected_at.map_or(true, |reconnected_at| {
+            reconnected_at.elapsed() >= SSH_RECONNECT_MAX_DELAY
+        }) {
+            self.ssh_reconnect_attempt = 0;
+        }
+        self.ssh_reconnect_attempt += 1;
+        if self.ssh_reconnect_attempt > SSH_RECONNECT_MAX_ATTEMPTS {
+            self.ssh_reconnect = Some(SshReconnect::GaveUp);
+            cx.notify();
+            return;
+        }
+        self.ssh_reconnect = Some(SshReconnect::Pending);
+        cx.notify();
+
+        let delay = terminal::ssh_reconnect_delay(self.ssh_reconnect_attempt);
+        let working_directory = self.terminal.read(cx).reported_working_directory();
+        let disconnected_terminal = self.terminal.downgrade();
+        let window = cx.window_handle();
+        cx.spawn(|terminal_view, mut cx| async move {
+            cx.background_executor().timer(delay).await;
+            // The user might have closed the terminal or opened another one in its place meanwhile.
+            let still_disconnected = terminal_view.update(&mut cx, |terminal_view, _| {
+                terminal_view.terminal.downgrade() == disconnected_terminal
+            })?;
+            if !still_disconnected {
+                return Ok(());
+            }
+            let terminal = project
+                .update(&mut cx, |project, cx| {
+                    let working_directory = working_directory
+                        .clone()
+                        .or_else(|| project.active_project_directory(cx));
+                    project.create_terminal(
+                        TerminalKind::Shell(working_directory),
+                        None,
+                        window,
+                        cx,
+                    )
+                })?
+                .await;
+            terminal_view.update(&mut cx, |terminal_view, cx| {
+                cx.notify();
+                let terminal = match terminal {
+                    Ok(terminal) => terminal,
+                    Err(error) => {
+                        terminal_view.ssh_reconnect = Some(SshReconnect::GaveUp);
+                        return Err(error);
+                    }
+                };
+                terminal_view.ssh_reconnect = None;
+                if let Some(working_directory) = working_directory {
+                    terminal.update(cx, |terminal, _| {
+                        terminal.set_reported_working_directory(working_directory)
+                    });
+                }
+                terminal_view.ssh_reconnected_at = Some(Instant::now());
+                terminal_view.set_terminal(terminal, cx);
+                anyhow::Ok(())
+            })?
+        })
+        .detach_and_log_err(cx);
+    }
+
     fn set_terminal(&mut self, terminal: Model<Terminal>, cx: &mut ViewContext<'_, TerminalView>) {
         self._terminal_subscriptions =
             subscribe_for_terminal_events(&terminal, self.workspace.clone(), cx);
@@ -780,6 +1094,12 @@ fn subscribe_for_terminal_events(
             },
             Event::BreadcrumbsChanged => cx.emit(ItemEvent::UpdateBreadcrumbs),
             Event::CloseTerminal => cx.emit(ItemEvent::CloseItem),
+            Event::TaskFinished => {
+                this.restart_crashed_task(cx);
+                cx.notify();
+            }
+            Event::TestResult(_) => {}
+            Event::SshDisconnected => this.reconnect_ssh(cx),
             Event::SelectionsChanged => {
                 cx.invalidate_character_coordinates();
                 cx.emit(SearchEvent::ActiveMatchChanged)
@@ -948,6 +1268,70 @@ impl TerminalView {
         });
         cx.notify();
     }
+
+    /// The label of the task's working directory, see [`task_cwd_label`].
+    fn task_cwd_label(&self, task: &TaskState, cx: &AppContext) -> Option<String> {
+        let worktree_roots = self
+            .workspace
+            .upgrade()
+            .map(|workspace| {
+                workspace
+                    .read(cx)
+                    .visible_worktrees(cx)
+                    .map(|worktree| {
+                        let worktree = worktree.read(cx);
+                        (worktree.root_name().to_string(), worktree.abs_path())
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        task_cwd_label(
+            task.spawned_task.cwd_label.as_deref(),
+            task.cwd.as_deref(),
+            &worktree_roots,
+        )
+        .or_else(|| task.cwd_label.clone())
+    }
+
+    /// The task's command and working directory, kept above the scrollback so that they do not scroll away with the output.
+    fn render_command_header(
+        command_label: String,
+        cwd_label: Option<String>,
+        cx: &mut ViewContext<Self>,
+    ) -> impl IntoElement {
+        h_flex()
+            .flex_none()
+            .w_full()
+            .px_2()
+            .py_1()
+            .gap_2()
+            .bg(cx.theme().colors().editor_subheader_background)
+            .border_b_1()
+            .border_color(cx.theme().colors().border_variant)
+            .child(
+                Label::new(command_label.clone())
+                    .size(LabelSize::Small)
+                    .color(Color::Muted)
+                    .single_line(),
+            )
+            .when_some(cwd_label, |this, cwd_label| {
+                this.child(
+                    Label::new(format!("in {cwd_label}"))
+                        .size(LabelSize::Small)
+                        .color(Color::Placeholder)
+                        .single_line(),
+                )
+            })
+            .child(div().flex_1())
+            .child(
+                IconButton::new("copy-task-command", IconName::Copy)
+                    .icon_size(IconSize::Small)
+                    .tooltip(|cx| Tooltip::text("Copy Command", cx))
+                    .on_click(move |_, cx| {
+                        cx.write_to_clipboard(ClipboardItem::new_string(command_label.clone()))
+                    }),
+            )
+    }
 }
 
 impl Render for TerminalView {
@@ -956,17 +1340,31 @@ impl Render for TerminalView {
         let terminal_view_handle = cx.view().clone();
 
         let focused = self.focus_handle.is_focused(cx);
+        let truncated_output = self
+            .terminal
+            .read(cx)
+            .task()
+            .filter(|task| task.output_truncated)
+            .and_then(|task| Some((task.max_output_lines?, task.status)));
+        let command_header = self
+            .terminal
+            .read(cx)
+            .task()
+            .filter(|task| task.show_command || task.show_summary)
+            .map(|task| (task.command_label.clone(), self.task_cwd_label(task, cx)));
 
-        div()
+        v_flex()
             .size_full()
             .relative()
             .track_focus(&self.focus_handle(cx))
             .key_context(self.dispatch_context(cx))
             .on_action(cx.listener(TerminalView::send_text))
             .on_action(cx.listener(TerminalView::send_keystroke))
+            .on_action(cx.listener(TerminalView::send_signal))
             .on_action(cx.listener(TerminalView::copy))
             .on_action(cx.listener(TerminalView::paste))
             .on_action(cx.listener(TerminalView::clear))
+            .on_action(cx.listener(TerminalView::clear_scrollback))
             .on_action(cx.listener(TerminalView::scroll_line_up))
             .on_action(cx.listener(TerminalView::scroll_line_down))
             .on_action(cx.listener(TerminalView::scroll_page_up))
@@ -974,6 +1372,9 @@ impl Render for TerminalView {
             .on_action(cx.listener(TerminalView::scroll_to_top))
             .on_action(cx.listener(TerminalView::scroll_to_bottom))
             .on_action(cx.listener(TerminalView::toggle_vi_mode))
+            .on_action(cx.listener(TerminalView::show_full_output))
+            .on_action(cx.listener(TerminalView::show_output_diff))
+            .on_action(cx.listener(TerminalView::export_scrollback))
             .on_action(cx.listener(TerminalView::show_character_palette))
             .on_action(cx.listener(TerminalView::select_all))
             .on_key_down(cx.listener(Self::key_down))
@@ -986,19 +1387,75 @@ impl Render for TerminalView {
                     }
                 }),
             )
+            .when_some(command_header, |this, (command_label, cwd_label)| {
+                this.child(Self::render_command_header(command_label, cwd_label, cx))
+            })
             .child(
                 // TODO: Oddly this wrapper div is needed for TerminalElement to not steal events from the context menu
-                div().size_full().child(TerminalElement::new(
-                    terminal_handle,
-                    terminal_view_handle,
-                    self.workspace.clone(),
-                    self.focus_handle.clone(),
-                    focused,
-                    self.should_show_cursor(focused, cx),
-                    self.can_navigate_to_selected_word,
-                    self.block_below_cursor.clone(),
-                )),
+                div()
+                    .flex_1()
+                    .min_h_0()
+                    .size_full()
+                    .child(TerminalElement::new(
+                        terminal_handle,
+                        terminal_view_handle,
+                        self.workspace.clone(),
+                        self.focus_handle.clone(),
+                        focused,
+                        self.should_show_cursor(focused, cx),
+                        self.can_navigate_to_selected_word,
+                        self.block_below_cursor.clone(),
+                    )),
             )
+            .when_some(truncated_output, |this, (max_output_lines, status)| {
+                let message = if status == TaskStatus::Running {
+                    format!("[Output truncated at {max_output_lines} lines. Task still running.]")
+                } else {
+                    format!("[Output truncated at {max_output_lines} lines.]")
+                };
+                this.child(
+                    h_flex()
+                        .absolute()
+                        .top_0()
+                        .right_0()
+                        .p_1()
+                        .gap_2()
+                        .bg(cx.theme().colors().elevated_surface_background)
+                        .child(
+                            Label::new(message)
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        )
+                        .child(
+                            Button::new("show-full-output", "Show Full Output")
+                                .label_size(LabelSize::Small)
+                                .on_click(cx.listener(|this, _, cx| {
+                                    this.show_full_output(&ShowFullOutput, cx)
+                                })),
+                        ),
+                )
+            })
+            .when_some(self.ssh_reconnect, |this, ssh_reconnect| {
+                let message = match ssh_reconnect {
+                    SshReconnect::Pending => {
+                        format!("[Reconnecting... attempt {}]", self.ssh_reconnect_attempt)
+                    }
+                    SshReconnect::GaveUp => "[Connection lost. Gave up reconnecting.]".to_string(),
+                };
+                this.child(
+                    h_flex()
+                        .absolute()
+                        .top_0()
+                        .right_0()
+                        .p_1()
+                        .bg(cx.theme().colors().elevated_surface_background)
+                        .child(
+                            Label::new(message)
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        ),
+                )
+            })
             .children(self.context_menu.as_ref().map(|(menu, position, _)| {
                 deferred(
                     anchored()
@@ -1015,7 +1472,16 @@ impl Item for TerminalView {
     type Event = ItemEvent;
 
     fn tab_tooltip_text(&self, cx: &AppContext) -> Option<SharedString> {
-        Some(self.terminal().read(cx).title(false).into())
+        let terminal = self.terminal().read(cx);
+        let title = terminal.title(false);
+        let group_summary = terminal
+            .task()
+            .and_then(|task| task.group_id.as_deref())
+            .and_then(|group_id| self.task_group_summary(group_id, cx));
+        match group_summary {
+            Some(group_summary) => Some(format!("{title}\n{group_summary}").into()),
+            None => Some(title.into()),
+        }
     }
 
     fn tab_content(&self, params: TabContentParams, cx: &WindowContext) -> AnyElement {
@@ -1058,6 +1524,11 @@ impl Item for TerminalView {
                         (IconName::XCircle, Color::Error, Some(rerun_button))
                     }
                 }
+                TaskStatus::Crashed { .. } => (
+                    IconName::XCircle,
+                    Color::Error,
+                    Some(rerun_button(terminal_task.id.clone())),
+                ),
             },
             None => (IconName::Terminal, Color::Muted, None),
         };
@@ -1147,7 +1618,7 @@ impl Item for TerminalView {
     }
 
     fn added_to_workspace(&mut self, workspace: &mut Workspace, cx: &mut ViewContext<Self>) {
-        if self.terminal().read(cx).task().is_none() {
+        if !self.terminal().read(cx).is_task_terminal() {
             if let Some((new_id, old_id)) = workspace.database_id().zip(self.workspace_id) {
                 cx.background_executor()
                     .spawn(TERMINAL_DB.update_workspace_id(new_id, old_id, cx.entity_id().as_u64()))
@@ -1183,7 +1654,7 @@ impl SerializableItem for TerminalView {
         cx: &mut ViewContext<Self>,
     ) -> Option<Task<gpui::Result<()>>> {
         let terminal = self.terminal().read(cx);
-        if terminal.task().is_some() {
+        if terminal.is_task_terminal() {
             return None;
         }
 
@@ -1231,9 +1702,11 @@ impl SerializableItem for TerminalView {
                 .ok()
                 .flatten();
 
-            let terminal = project.update(&mut cx, |project, cx| {
-                project.create_terminal(TerminalKind::Shell(cwd), window, cx)
-            })??;
+            let terminal = project
+                .update(&mut cx, |project, cx| {
+                    project.create_terminal(TerminalKind::Shell(cwd), None, window, cx)
+                })?
+                .await?;
             cx.update(|cx| {
                 cx.new_view(|cx| TerminalView::new(terminal, workspace, Some(workspace_id), cx))
             })
@@ -1386,6 +1859,36 @@ fn first_project_directory(workspace: &Workspace, cx: &AppContext) -> Option<Pat
     Some(worktree.abs_path().to_path_buf())
 }
 
+/// The label of a task's working directory: the task's own `cwd_label` if it has one (e.g. the path on the remote host),
+/// otherwise the working directory relative to the worktrees.
+fn task_cwd_label(
+    cwd_label: Option<&str>,
+    cwd: Option<&Path>,
+    worktree_roots: &[(String, Arc<Path>)],
+) -> Option<String> {
+    cwd_label
+        .map(str::to_string)
+        .or_else(|| relative_task_cwd(cwd?, worktree_roots))
+}
+
+/// The directory relative to the innermost worktree containing it, e.g. `crates/project`,
+/// prefixed with the worktree's name when there are several worktrees to tell apart.
+fn relative_task_cwd(cwd: &Path, worktree_roots: &[(String, Arc<Path>)]) -> Option<String> {
+    let (root_name, relative_cwd) = worktree_roots
+        .iter()
+        .filter_map(|(root_name, root)| Some((root_name, root, cwd.strip_prefix(root).ok()?)))
+        .max_by_key(|(_, root, _)| root.components().count())
+        .map(|(root_name, _, relative_cwd)| (root_name, relative_cwd))?;
+    let label = if worktree_roots.len() > 1 {
+        Path::new(root_name).join(relative_cwd)
+    } else if relative_cwd.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        relative_cwd.to_path_buf()
+    };
+    Some(label.to_string_lossy().to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1584,4 +2087,50 @@ mod tests {
     fn empty_string_stays_empty() {
         assert_eq!(regex_to_literal(""), "".to_string());
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn relative_task_cwd_labels() {
+        let root = |name: &str, path: &str| (name.to_string(), Arc::from(Path::new(path)));
+        let single_root = [root("zed", "/work/zed")];
+        assert_eq!(
+            relative_task_cwd(Path::new("/work/zed/crates/project"), &single_root).as_deref(),
+            Some("crates/project")
+        );
+        assert_eq!(
+            relative_task_cwd(Path::new("/work/zed"), &single_root).as_deref(),
+            Some(".")
+        );
+        assert_eq!(relative_task_cwd(Path::new("/tmp"), &single_root), None);
+
+        let multiple_roots = [
+            root("zed", "/work/zed"),
+            root("docs", "/work/docs"),
+            root("nested", "/work/zed/nested"),
+        ];
+        assert_eq!(
+            relative_task_cwd(Path::new("/work/docs/src"), &multiple_roots).as_deref(),
+            Some("docs/src")
+        );
+        assert_eq!(
+            relative_task_cwd(Path::new("/work/zed/nested/src"), &multiple_roots).as_deref(),
+            Some("nested/src")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn task_cwd_label_prefers_explicit_label() {
+        let roots = [("zed".to_string(), Arc::from(Path::new("/work/zed")))];
+        let cwd = Path::new("/work/zed/crates/project");
+        assert_eq!(
+            task_cwd_label(Some("/home/remote/zed/crates/project"), Some(cwd), &roots).as_deref(),
+            Some("/home/remote/zed/crates/project")
+        );
+        assert_eq!(
+            task_cwd_label(None, Some(cwd), &roots).as_deref(),
+            Some("crates/project")
+        );
+        assert_eq!(task_cwd_label(None, None, &roots), None);
+    }
 }
@@ -301,6 +301,8 @@ pub struct Pane {
     /// Otherwise, when `display_nav_history_buttons` is Some, it determines whether nav buttons should be displayed.
     display_nav_history_buttons: Option<bool>,
     double_click_dispatch_action: Box<dyn Action>,
+    /// Whether double-clicking a tab toggles the pane's zoom.
+    zoom_on_tab_double_click: bool,
     save_modals_spawned: HashSet<EntityId>,
     pub new_item_context_menu_handle: PopoverMenuHandle<ContextMenu>,
     pub split_item_context_menu_handle: PopoverMenuHandle<ContextMenu>,
@@ -500,6 +502,7 @@ impl Pane {
             ),
             _subscriptions: subscriptions,
             double_click_dispatch_action,
+            zoom_on_tab_double_click: false,
             save_modals_spawned: HashSet::default(),
             split_item_context_menu_handle: Default::default(),
             new_item_context_menu_handle: Default::default(),
@@ -623,6 +626,10 @@ impl Pane {
         self.should_display_tab_bar = Rc::new(should_display_tab_bar);
     }
 
+    pub fn set_zoom_on_tab_double_click(&mut self, zoom_on_tab_double_click: bool) {
+        self.zoom_on_tab_double_click = zoom_on_tab_double_click;
+    }
+
     pub fn set_can_split(
         &mut self,
         can_split_predicate: Option<
@@ -1991,6 +1998,9 @@ impl Pane {
                             pane.set_preview_item_id(None, cx);
                         }
                     }
+                    if pane.zoom_on_tab_double_click && event.click_count == 2 {
+                        pane.toggle_zoom(&ToggleZoom, cx);
+                    }
                 }),
             )
             .on_drag(